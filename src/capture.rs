@@ -0,0 +1,20 @@
+//! Loading captured SV2 traffic, either pasted as hex or exported to a file.
+
+use std::path::Path;
+
+/// Parse hex text as exported by capture tools: whitespace (including line
+/// breaks) and a leading `0x` are ignored.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    let digits: String = text.split_whitespace().collect();
+    hex::decode(digits.trim_start_matches("0x"))
+}
+
+/// Read a capture file. Files containing only hex text are decoded, anything
+/// else is taken as raw bytes.
+pub fn read_file(path: impl AsRef<Path>) -> std::io::Result<Vec<u8>> {
+    let contents = std::fs::read(path)?;
+    let hex = std::str::from_utf8(&contents)
+        .ok()
+        .and_then(|text| parse_hex(text).ok());
+    Ok(hex.unwrap_or(contents))
+}
@@ -0,0 +1,163 @@
+//! Common messages shared by every subprotocol (`common_messages_sv2`).
+
+use common_messages_sv2::{
+    ChannelEndpointChanged, Protocol, Reconnect, SetupConnection, SetupConnectionError,
+    SetupConnectionSuccess, MESSAGE_TYPE_CHANNEL_ENDPOINT_CHANGED, MESSAGE_TYPE_RECONNECT,
+    MESSAGE_TYPE_SETUP_CONNECTION, MESSAGE_TYPE_SETUP_CONNECTION_ERROR,
+    MESSAGE_TYPE_SETUP_CONNECTION_SUCCESS,
+};
+use serde_json::{json, Value};
+
+use super::fields::*;
+use super::Direction::*;
+use super::FieldType::*;
+use super::{from_bytes, to_bytes, CodecError, MessageDescriptor, Subprotocol};
+
+pub(super) const MESSAGES: &[MessageDescriptor] = &[
+    MessageDescriptor::new(Subprotocol::Common, "SetupConnection", MESSAGE_TYPE_SETUP_CONNECTION, false, ClientToServer, &[("protocol", U8), ("min_version", U16), ("max_version", U16), ("flags", U32), ("endpoint_host", Str0255), ("endpoint_port", U16), ("vendor", Str0255), ("hardware_version", Str0255), ("firmware", Str0255), ("device_id", Str0255)]),
+    MessageDescriptor::new(Subprotocol::Common, "SetupConnectionSuccess", MESSAGE_TYPE_SETUP_CONNECTION_SUCCESS, false, ServerToClient, &[("used_version", U16), ("flags", U32)]),
+    MessageDescriptor::new(Subprotocol::Common, "SetupConnectionError", MESSAGE_TYPE_SETUP_CONNECTION_ERROR, false, ServerToClient, &[("flags", U32), ("error_code", Str0255)]),
+    MessageDescriptor::new(Subprotocol::Common, "ChannelEndpointChanged", MESSAGE_TYPE_CHANNEL_ENDPOINT_CHANGED, true, ServerToClient, &[("channel_id", U32)]),
+    MessageDescriptor::new(Subprotocol::Common, "Reconnect", MESSAGE_TYPE_RECONNECT, false, ServerToClient, &[("new_host", Str0255), ("new_port", U16)]),
+];
+
+pub(super) fn encode(name: &str, message: &Value) -> Result<Vec<u8>, CodecError> {
+    match name {
+        "SetupConnection" => to_bytes(SetupConnection {
+            protocol: protocol_field(message, "protocol")?,
+            min_version: u16_field(message, "min_version")?,
+            max_version: u16_field(message, "max_version")?,
+            flags: u32_field(message, "flags")?,
+            endpoint_host: str0255_field(message, "endpoint_host")?,
+            endpoint_port: u16_field(message, "endpoint_port")?,
+            vendor: str0255_field(message, "vendor")?,
+            hardware_version: str0255_field(message, "hardware_version")?,
+            firmware: str0255_field(message, "firmware")?,
+            device_id: str0255_field(message, "device_id")?,
+        }),
+        "SetupConnectionSuccess" => to_bytes(SetupConnectionSuccess {
+            used_version: u16_field(message, "used_version")?,
+            flags: u32_field(message, "flags")?,
+        }),
+        "SetupConnectionError" => to_bytes(SetupConnectionError {
+            flags: u32_field(message, "flags")?,
+            error_code: str0255_field(message, "error_code")?,
+        }),
+        "ChannelEndpointChanged" => to_bytes(ChannelEndpointChanged {
+            channel_id: u32_field(message, "channel_id")?,
+        }),
+        "Reconnect" => to_bytes(Reconnect {
+            new_host: str0255_field(message, "new_host")?,
+            new_port: u16_field(message, "new_port")?,
+        }),
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
+
+pub(super) fn decode(name: &str, payload: &mut [u8]) -> Result<Value, CodecError> {
+    match name {
+        "SetupConnection" => {
+            let m: SetupConnection = from_bytes(payload)?;
+            Ok(json!({
+                "protocol": m.protocol as u8,
+                "min_version": m.min_version,
+                "max_version": m.max_version,
+                "flags": m.flags,
+                "endpoint_host": str_json("endpoint_host", &m.endpoint_host)?,
+                "endpoint_port": m.endpoint_port,
+                "vendor": str_json("vendor", &m.vendor)?,
+                "hardware_version": str_json("hardware_version", &m.hardware_version)?,
+                "firmware": str_json("firmware", &m.firmware)?,
+                "device_id": str_json("device_id", &m.device_id)?,
+            }))
+        }
+        "SetupConnectionSuccess" => {
+            let m: SetupConnectionSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "used_version": m.used_version,
+                "flags": m.flags,
+            }))
+        }
+        "SetupConnectionError" => {
+            let m: SetupConnectionError = from_bytes(payload)?;
+            Ok(json!({
+                "flags": m.flags,
+                "error_code": str_json("error_code", &m.error_code)?,
+            }))
+        }
+        "ChannelEndpointChanged" => {
+            let m: ChannelEndpointChanged = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+            }))
+        }
+        "Reconnect" => {
+            let m: Reconnect = from_bytes(payload)?;
+            Ok(json!({
+                "new_host": str_json("new_host", &m.new_host)?,
+                "new_port": m.new_port,
+            }))
+        }
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
+
+/// Subprotocol names accepted for the `protocol` field of SetupConnection.
+pub(super) const PROTOCOL_NAMES: &[(&str, u8)] = &[
+    ("MiningProtocol", 0),
+    ("mining", 0),
+    ("JobDeclarationProtocol", 1),
+    ("job_declaration", 1),
+    ("TemplateDistributionProtocol", 2),
+    ("template_distribution", 2),
+];
+
+/// Largest valid value of an integer field that is narrower than its type:
+/// SetupConnection's protocol only takes the defined discriminants.
+pub(super) fn integer_field_max(descriptor: &MessageDescriptor, name: &str) -> Option<u64> {
+    (descriptor.subprotocol == Subprotocol::Common && name == "protocol")
+        .then(|| PROTOCOL_NAMES.iter().map(|(_, discriminant)| *discriminant as u64).max().unwrap_or(0))
+}
+
+/// `protocol` is a U8 discriminant; the subprotocol names are accepted too.
+fn protocol_field(message: &Value, name: &str) -> Result<Protocol, CodecError> {
+    let named = field(message, name)?
+        .as_str()
+        .and_then(|text| PROTOCOL_NAMES.iter().find(|(protocol, _)| *protocol == text));
+    let discriminant = match named {
+        Some((_, discriminant)) => *discriminant,
+        None => u8_field(message, name)?,
+    };
+    Protocol::try_from(discriminant).map_err(|_| CodecError::InvalidField {
+        field: name.to_string(),
+        reason: format!("{} is not a valid subprotocol (0 = mining, 1 = job declaration, 2 = template distribution)", discriminant),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::super::{default_message, encode, json_schema, lookup};
+    use super::*;
+
+    #[test]
+    fn protocol_maximum_is_the_last_subprotocol() {
+        let descriptor = lookup("SetupConnection").unwrap();
+        // Each subprotocol has a long and a short name
+        let subprotocols: BTreeSet<u8> = PROTOCOL_NAMES.iter().map(|(_, discriminant)| *discriminant).collect();
+        let maximum = subprotocols.len() as u64 - 1;
+        assert_eq!(integer_field_max(descriptor, "protocol"), Some(maximum));
+        assert_eq!(json_schema(descriptor)["properties"]["protocol"]["anyOf"][0]["maximum"], json!(maximum));
+
+        let mut message = default_message(descriptor);
+        for (name, discriminant) in PROTOCOL_NAMES {
+            message["protocol"] = json!(name);
+            let by_name = encode("SetupConnection", &message).unwrap();
+            message["protocol"] = json!(discriminant);
+            assert_eq!(encode("SetupConnection", &message).unwrap(), by_name, "{}", name);
+        }
+        message["protocol"] = json!(maximum + 1);
+        assert!(matches!(encode("SetupConnection", &message), Err(CodecError::InvalidField { field, .. }) if field == "protocol"));
+    }
+}
@@ -0,0 +1,215 @@
+//! Conversions between JSON message fields and SV2 binary data types.
+//!
+//! Integers are accepted as JSON numbers, decimal strings or `0x`-prefixed hex
+//! strings. Binary fields (U256, B0_32, B0_255, B0_64K, B0_16M) are hex strings,
+//! STR0_255 fields are plain strings and OPTION fields may be `null`. Decoding
+//! produces the same representation so decoded messages can be re-encoded.
+
+use std::convert::TryFrom;
+
+use binary_sv2::{Seq0255, Seq064K, Str0255, Sv2Option};
+use serde_json::Value;
+
+use super::{CodecError, FieldType};
+
+pub(crate) fn field<'a>(message: &'a Value, name: &str) -> Result<&'a Value, CodecError> {
+    message
+        .get(name)
+        .ok_or_else(|| CodecError::MissingField(name.to_string()))
+}
+
+fn invalid(name: &str, reason: impl Into<String>) -> CodecError {
+    CodecError::InvalidField {
+        field: name.to_string(),
+        reason: reason.into(),
+    }
+}
+
+pub(crate) fn parse_integer(name: &str, value: &Value) -> Result<u64, CodecError> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .ok_or_else(|| invalid(name, format!("{} is not a non-negative integer", number))),
+        Value::String(text) => {
+            let parsed = match text.strip_prefix("0x") {
+                Some(digits) => u64::from_str_radix(digits, 16),
+                None => text.parse::<u64>(),
+            };
+            parsed.map_err(|_| invalid(name, format!("{:?} is not an integer", text)))
+        }
+        other => Err(invalid(name, format!("expected an integer, got {}", other))),
+    }
+}
+
+fn integer<T: TryFrom<u64>>(message: &Value, name: &str, type_name: &str) -> Result<T, CodecError> {
+    let value = parse_integer(name, field(message, name)?)?;
+    T::try_from(value).map_err(|_| invalid(name, format!("{} does not fit in {}", value, type_name)))
+}
+
+pub(crate) fn u8_field(message: &Value, name: &str) -> Result<u8, CodecError> {
+    integer(message, name, "U8")
+}
+
+pub(crate) fn u16_field(message: &Value, name: &str) -> Result<u16, CodecError> {
+    integer(message, name, "U16")
+}
+
+pub(crate) fn u32_field(message: &Value, name: &str) -> Result<u32, CodecError> {
+    integer(message, name, "U32")
+}
+
+pub(crate) fn u64_field(message: &Value, name: &str) -> Result<u64, CodecError> {
+    integer(message, name, "U64")
+}
+
+pub(crate) fn f32_field(message: &Value, name: &str) -> Result<f32, CodecError> {
+    field(message, name)?
+        .as_f64()
+        .map(|value| value as f32)
+        .ok_or_else(|| invalid(name, "expected a number"))
+}
+
+pub(crate) fn bool_field(message: &Value, name: &str) -> Result<bool, CodecError> {
+    field(message, name)?
+        .as_bool()
+        .ok_or_else(|| invalid(name, "expected a boolean"))
+}
+
+pub(crate) fn str0255_field(message: &Value, name: &str) -> Result<Str0255<'static>, CodecError> {
+    let text = field(message, name)?
+        .as_str()
+        .ok_or_else(|| invalid(name, "expected a string"))?;
+    Str0255::try_from(text.to_string())
+        .map_err(|_| invalid(name, format!("{} bytes exceeds STR0_255 maximum of 255", text.len())))
+}
+
+fn hex_value(name: &str, value: &Value) -> Result<Vec<u8>, CodecError> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| invalid(name, "expected a hex string"))?;
+    hex::decode(text.trim_start_matches("0x"))
+        .map_err(|e| invalid(name, format!("invalid hex: {}", e)))
+}
+
+fn sized<T>(name: &str, bytes: Vec<u8>, type_name: &str) -> Result<T, CodecError>
+where
+    T: TryFrom<Vec<u8>, Error = binary_sv2::Error>,
+{
+    let len = bytes.len();
+    T::try_from(bytes).map_err(|_| invalid(name, format!("{} bytes is not a valid {}", len, type_name)))
+}
+
+/// Hex-encoded binary field; `type_name` is only used in error messages.
+pub(crate) fn bytes_field<T>(message: &Value, name: &str, type_name: &str) -> Result<T, CodecError>
+where
+    T: TryFrom<Vec<u8>, Error = binary_sv2::Error>,
+{
+    sized(name, hex_value(name, field(message, name)?)?, type_name)
+}
+
+fn array_field<'a>(message: &'a Value, name: &str) -> Result<&'a Vec<Value>, CodecError> {
+    field(message, name)?
+        .as_array()
+        .ok_or_else(|| invalid(name, "expected an array"))
+}
+
+/// Empty stand-in for a SEQ0_255 field. `binary_codec_sv2` overflows (and
+/// panics in debug builds) sizing a sequence of 255 items, so `super::encode`
+/// writes every sequence with `sequence_bytes` instead.
+pub(crate) fn empty_seq0255<T: 'static>() -> Seq0255<'static, T> {
+    Seq0255::new(Vec::new()).expect("an empty sequence fits")
+}
+
+/// Empty stand-in for a SEQ0_64K field; see `empty_seq0255`.
+pub(crate) fn empty_seq064k<T: 'static>() -> Seq064K<'static, T> {
+    Seq064K::new(Vec::new()).expect("an empty sequence fits")
+}
+
+fn sequence_item(name: &str, item_type: FieldType, item: &Value, bytes: &mut Vec<u8>) -> Result<(), CodecError> {
+    match item_type {
+        FieldType::U16 => {
+            let value = parse_integer(name, item)?;
+            let value = u16::try_from(value).map_err(|_| invalid(name, format!("{} does not fit in U16", value)))?;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        FieldType::U32 => {
+            let value = parse_integer(name, item)?;
+            let value = u32::try_from(value).map_err(|_| invalid(name, format!("{} does not fit in U32", value)))?;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        FieldType::U256 => {
+            let value = hex_value(name, item)?;
+            if value.len() != 32 {
+                return Err(invalid(name, format!("{} bytes is not a valid U256", value.len())));
+            }
+            bytes.extend_from_slice(&value);
+        }
+        FieldType::B016M => {
+            let value = hex_value(name, item)?;
+            if value.len() > 0xff_ffff {
+                return Err(invalid(name, format!("{} bytes is not a valid B0_16M", value.len())));
+            }
+            bytes.extend_from_slice(&(value.len() as u32).to_le_bytes()[..3]);
+            bytes.extend_from_slice(&value);
+        }
+        other => {
+            return Err(invalid(name, format!("{} items are not supported", other.spec_name())));
+        }
+    }
+    Ok(())
+}
+
+/// Count prefix and items of a SEQ0_255 or SEQ0_64K field.
+pub(crate) fn sequence_bytes(message: &Value, name: &str, field_type: FieldType) -> Result<Vec<u8>, CodecError> {
+    let items = array_field(message, name)?;
+    let (item_type, mut bytes) = match field_type {
+        FieldType::Seq0255(item) => {
+            let count = u8::try_from(items.len())
+                .map_err(|_| invalid(name, format!("{} items exceeds SEQ0_255 maximum of 255", items.len())))?;
+            (*item, vec![count])
+        }
+        FieldType::Seq064K(item) => {
+            let count = u16::try_from(items.len())
+                .map_err(|_| invalid(name, format!("{} items exceeds SEQ0_64K maximum of 65535", items.len())))?;
+            (*item, count.to_le_bytes().to_vec())
+        }
+        other => return Err(invalid(name, format!("{} is not a sequence", other.spec_name()))),
+    };
+    for item in items {
+        sequence_item(name, item_type, item, &mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+/// OPTION[U32]: a missing or `null` field encodes as absent.
+pub(crate) fn option_u32_field(message: &Value, name: &str) -> Result<Sv2Option<'static, u32>, CodecError> {
+    match message.get(name) {
+        None | Some(Value::Null) => Ok(Sv2Option::new(None)),
+        Some(_) => Ok(Sv2Option::new(Some(u32_field(message, name)?))),
+    }
+}
+
+/// Render an F32 via its shortest decimal form so 100.1 does not become 100.09999847.
+pub(crate) fn f32_json(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+pub(crate) fn hex_json(bytes: &[u8]) -> Value {
+    Value::String(hex::encode(bytes))
+}
+
+pub(crate) fn hex_list_json(items: Vec<Vec<u8>>) -> Value {
+    Value::Array(items.iter().map(|item| hex_json(item)).collect())
+}
+
+pub(crate) fn str_json(name: &str, value: &Str0255) -> Result<Value, CodecError> {
+    std::str::from_utf8(value.inner_as_ref())
+        .map(|text| Value::String(text.to_string()))
+        .map_err(|e| invalid(name, format!("STR0_255 is not valid UTF-8: {}", e)))
+}
@@ -0,0 +1,112 @@
+//! SV2 frame headers (`framing_sv2`).
+//!
+//! A frame is a 6-byte header followed by the message payload: extension_type
+//! (U16, bit 15 is the channel_msg flag), msg_type (U8) and msg_length (U24,
+//! the payload length excluding the header).
+
+use framing_sv2::{framing::Sv2Frame, header::Header};
+
+use super::{lookup, CodecError};
+use crate::types::FrameHeader;
+
+pub const FRAME_HEADER_SIZE: usize = Header::SIZE;
+
+pub(super) const CHANNEL_MSG_BIT: u16 = 0x8000;
+
+/// Frames are built around an already encoded payload, so the message type is
+/// the raw `Vec<u8>`, as `framing_sv2` does for frames read off the wire.
+type RawFrame = Sv2Frame<Vec<u8>, Vec<u8>>;
+
+/// Resolve the msg_type and default channel_msg flag for a frame, from either a
+/// message name known to the codec or a numeric msg_type such as `0x1a`.
+pub fn frame_message_type(message_type: &str) -> Result<(u8, bool), CodecError> {
+    match lookup(message_type) {
+        Ok(descriptor) => Ok((descriptor.msg_type, descriptor.channel_msg)),
+        Err(CodecError::UnknownMessageType(_)) => {
+            let parsed = match message_type.strip_prefix("0x") {
+                Some(digits) => u8::from_str_radix(digits, 16),
+                None => message_type.parse::<u8>(),
+            };
+            parsed
+                .map(|msg_type| (msg_type, false))
+                .map_err(|_| CodecError::UnknownMessageType(message_type.to_string()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Prefix an encoded payload with its frame header.
+pub fn encode_frame(
+    extension_type: u16,
+    msg_type: u8,
+    channel_msg: bool,
+    payload: &[u8],
+) -> Result<(FrameHeader, Vec<u8>), CodecError> {
+    let frame = RawFrame::from_message(payload.to_vec(), msg_type, extension_type, channel_msg)
+        .ok_or_else(|| {
+            CodecError::InvalidFrame(format!("{} byte payload exceeds the U24 msg_length maximum", payload.len()))
+        })?;
+    let header = frame.get_header().expect("Sv2Frame always has a header");
+    let mut bytes = binary_sv2::to_bytes(header)?;
+    bytes.extend_from_slice(payload);
+    Ok((header_fields(&header, &bytes), bytes))
+}
+
+/// Split a frame into its header and the bytes that follow it. The payload is
+/// returned as present in the input, which may differ from msg_length.
+pub fn decode_frame(bytes: &[u8]) -> Result<(FrameHeader, &[u8]), CodecError> {
+    let header = Header::from_bytes(bytes).map_err(|_| {
+        CodecError::InvalidFrame(format!("{} bytes is shorter than the {} byte header", bytes.len(), Header::SIZE))
+    })?;
+    Ok((header_fields(&header, bytes), &bytes[Header::SIZE..]))
+}
+
+fn header_fields(header: &Header, bytes: &[u8]) -> FrameHeader {
+    FrameHeader {
+        extension_type: header.ext_type() & !CHANNEL_MSG_BIT,
+        // `Header::channel_msg` tests the wrong bit, so read bit 15 directly.
+        channel_msg: header.ext_type() & CHANNEL_MSG_BIT != 0,
+        msg_type: header.msg_type(),
+        // `Header` does not expose msg_length outside its crate.
+        msg_length: u32::from_le_bytes([bytes[3], bytes[4], bytes[5], 0]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::decode_frame_message;
+
+    #[test]
+    fn channel_bit_round_trips() {
+        for (extension_type, channel_msg) in [(0x0000, false), (0x0000, true), (0x0002, true), (0x7fff, false)] {
+            let (header, bytes) = encode_frame(extension_type, 0x1b, channel_msg, &[0xaa, 0xbb]).unwrap();
+            let wire_extension_type = extension_type | if channel_msg { CHANNEL_MSG_BIT } else { 0 };
+            assert_eq!(bytes[..2], wire_extension_type.to_le_bytes());
+            assert_eq!((header.extension_type, header.channel_msg), (extension_type, channel_msg));
+
+            let (decoded, payload) = decode_frame(&bytes).unwrap();
+            assert_eq!((decoded.extension_type, decoded.channel_msg), (extension_type, channel_msg));
+            assert_eq!((decoded.msg_type, decoded.msg_length), (0x1b, 2));
+            assert_eq!(payload, [0xaa, 0xbb]);
+        }
+    }
+
+    #[test]
+    fn msg_length_is_reported_as_declared() {
+        // SetupConnectionSuccess declaring 6 payload bytes
+        let (_, frame) = encode_frame(0, 0x01, false, &[0x02, 0x00, 0x00, 0x00, 0x00, 0x00]).unwrap();
+        assert!(decode_frame_message(&frame).is_ok());
+
+        for bytes in [frame[..frame.len() - 1].to_vec(), [frame.as_slice(), &[0x00]].concat()] {
+            let (header, payload) = decode_frame(&bytes).unwrap();
+            assert_eq!(header.msg_length, 6);
+            assert_eq!(payload.len(), bytes.len() - FRAME_HEADER_SIZE);
+            let error = decode_frame_message(&bytes).unwrap_err().to_string();
+            assert_eq!(
+                error,
+                format!("Invalid frame: msg_length declares 6 bytes but {} follow the header", payload.len())
+            );
+        }
+    }
+}
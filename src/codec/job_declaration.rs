@@ -0,0 +1,142 @@
+//! Job Declaration protocol messages (`job_declaration_sv2`).
+
+use job_declaration_sv2::*;
+use serde_json::{json, Value};
+
+use super::fields::*;
+use super::Direction::*;
+use super::FieldType::*;
+use super::{from_bytes, to_bytes, CodecError, MessageDescriptor, Subprotocol};
+
+pub(super) const MESSAGES: &[MessageDescriptor] = &[
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "AllocateMiningJobToken", MESSAGE_TYPE_ALLOCATE_MINING_JOB_TOKEN, CHANNEL_BIT_ALLOCATE_MINING_JOB_TOKEN, ClientToServer, &[("user_identifier", Str0255), ("request_id", U32)]),
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "AllocateMiningJobTokenSuccess", MESSAGE_TYPE_ALLOCATE_MINING_JOB_TOKEN_SUCCESS, CHANNEL_BIT_ALLOCATE_MINING_JOB_TOKEN_SUCCESS, ServerToClient, &[("request_id", U32), ("mining_job_token", B0255), ("coinbase_outputs", B064K)]),
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "ProvideMissingTransactions", MESSAGE_TYPE_PROVIDE_MISSING_TRANSACTIONS, CHANNEL_BIT_PROVIDE_MISSING_TRANSACTIONS, ServerToClient, &[("request_id", U32), ("unknown_tx_position_list", Seq064K(&U16))]),
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "ProvideMissingTransactionsSuccess", MESSAGE_TYPE_PROVIDE_MISSING_TRANSACTIONS_SUCCESS, CHANNEL_BIT_PROVIDE_MISSING_TRANSACTIONS_SUCCESS, ClientToServer, &[("request_id", U32), ("transaction_list", Seq064K(&B016M))]),
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "DeclareMiningJob", MESSAGE_TYPE_DECLARE_MINING_JOB, CHANNEL_BIT_DECLARE_MINING_JOB, ClientToServer, &[("request_id", U32), ("mining_job_token", B0255), ("version", U32), ("coinbase_prefix", B064K), ("coinbase_suffix", B064K), ("tx_ids_list", Seq064K(&U256)), ("excess_data", B064K)]),
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "DeclareMiningJobSuccess", MESSAGE_TYPE_DECLARE_MINING_JOB_SUCCESS, CHANNEL_BIT_DECLARE_MINING_JOB_SUCCESS, ServerToClient, &[("request_id", U32), ("new_mining_job_token", B0255)]),
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "DeclareMiningJobError", MESSAGE_TYPE_DECLARE_MINING_JOB_ERROR, CHANNEL_BIT_DECLARE_MINING_JOB_ERROR, ServerToClient, &[("request_id", U32), ("error_code", Str0255), ("error_details", B064K)]),
+    MessageDescriptor::new(Subprotocol::JobDeclaration, "PushSolution", MESSAGE_TYPE_PUSH_SOLUTION, CHANNEL_BIT_SUBMIT_SOLUTION_JD, ClientToServer, &[("extranonce", B032), ("prev_hash", U256), ("ntime", U32), ("nonce", U32), ("nbits", U32), ("version", U32)]),
+];
+
+pub(super) fn encode(name: &str, message: &Value) -> Result<Vec<u8>, CodecError> {
+    match name {
+        "AllocateMiningJobToken" => to_bytes(AllocateMiningJobToken {
+            user_identifier: str0255_field(message, "user_identifier")?,
+            request_id: u32_field(message, "request_id")?,
+        }),
+        "AllocateMiningJobTokenSuccess" => to_bytes(AllocateMiningJobTokenSuccess {
+            request_id: u32_field(message, "request_id")?,
+            mining_job_token: bytes_field(message, "mining_job_token", "B0_255")?,
+            coinbase_outputs: bytes_field(message, "coinbase_outputs", "B0_64K")?,
+        }),
+        "ProvideMissingTransactions" => to_bytes(ProvideMissingTransactions {
+            request_id: u32_field(message, "request_id")?,
+            unknown_tx_position_list: empty_seq064k(),
+        }),
+        "ProvideMissingTransactionsSuccess" => to_bytes(ProvideMissingTransactionsSuccess {
+            request_id: u32_field(message, "request_id")?,
+            transaction_list: empty_seq064k(),
+        }),
+        "DeclareMiningJob" => to_bytes(DeclareMiningJob {
+            request_id: u32_field(message, "request_id")?,
+            mining_job_token: bytes_field(message, "mining_job_token", "B0_255")?,
+            version: u32_field(message, "version")?,
+            coinbase_prefix: bytes_field(message, "coinbase_prefix", "B0_64K")?,
+            coinbase_suffix: bytes_field(message, "coinbase_suffix", "B0_64K")?,
+            tx_ids_list: empty_seq064k(),
+            excess_data: bytes_field(message, "excess_data", "B0_64K")?,
+        }),
+        "DeclareMiningJobSuccess" => to_bytes(DeclareMiningJobSuccess {
+            request_id: u32_field(message, "request_id")?,
+            new_mining_job_token: bytes_field(message, "new_mining_job_token", "B0_255")?,
+        }),
+        "DeclareMiningJobError" => to_bytes(DeclareMiningJobError {
+            request_id: u32_field(message, "request_id")?,
+            error_code: str0255_field(message, "error_code")?,
+            error_details: bytes_field(message, "error_details", "B0_64K")?,
+        }),
+        "PushSolution" => to_bytes(PushSolution {
+            extranonce: bytes_field(message, "extranonce", "B0_32")?,
+            prev_hash: bytes_field(message, "prev_hash", "U256")?,
+            ntime: u32_field(message, "ntime")?,
+            nonce: u32_field(message, "nonce")?,
+            nbits: u32_field(message, "nbits")?,
+            version: u32_field(message, "version")?,
+        }),
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
+
+pub(super) fn decode(name: &str, payload: &mut [u8]) -> Result<Value, CodecError> {
+    match name {
+        "AllocateMiningJobToken" => {
+            let m: AllocateMiningJobToken = from_bytes(payload)?;
+            Ok(json!({
+                "user_identifier": str_json("user_identifier", &m.user_identifier)?,
+                "request_id": m.request_id,
+            }))
+        }
+        "AllocateMiningJobTokenSuccess" => {
+            let m: AllocateMiningJobTokenSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "mining_job_token": hex_json(m.mining_job_token.inner_as_ref()),
+                "coinbase_outputs": hex_json(m.coinbase_outputs.inner_as_ref()),
+            }))
+        }
+        "ProvideMissingTransactions" => {
+            let m: ProvideMissingTransactions = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "unknown_tx_position_list": m.unknown_tx_position_list.into_inner(),
+            }))
+        }
+        "ProvideMissingTransactionsSuccess" => {
+            let m: ProvideMissingTransactionsSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "transaction_list": hex_list_json(m.transaction_list.to_vec()),
+            }))
+        }
+        "DeclareMiningJob" => {
+            let m: DeclareMiningJob = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "mining_job_token": hex_json(m.mining_job_token.inner_as_ref()),
+                "version": m.version,
+                "coinbase_prefix": hex_json(m.coinbase_prefix.inner_as_ref()),
+                "coinbase_suffix": hex_json(m.coinbase_suffix.inner_as_ref()),
+                "tx_ids_list": hex_list_json(m.tx_ids_list.to_vec()),
+                "excess_data": hex_json(m.excess_data.inner_as_ref()),
+            }))
+        }
+        "DeclareMiningJobSuccess" => {
+            let m: DeclareMiningJobSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "new_mining_job_token": hex_json(m.new_mining_job_token.inner_as_ref()),
+            }))
+        }
+        "DeclareMiningJobError" => {
+            let m: DeclareMiningJobError = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "error_code": str_json("error_code", &m.error_code)?,
+                "error_details": hex_json(m.error_details.inner_as_ref()),
+            }))
+        }
+        "PushSolution" => {
+            let m: PushSolution = from_bytes(payload)?;
+            Ok(json!({
+                "extranonce": hex_json(m.extranonce.inner_as_ref()),
+                "prev_hash": hex_json(m.prev_hash.inner_as_ref()),
+                "ntime": m.ntime,
+                "nonce": m.nonce,
+                "nbits": m.nbits,
+                "version": m.version,
+            }))
+        }
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
@@ -0,0 +1,276 @@
+//! Wire layout of message fields, used to map each encoded byte back to the
+//! field it belongs to.
+
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+
+use super::fields::f32_json;
+use super::frame::{CHANNEL_MSG_BIT, FRAME_HEADER_SIZE};
+use super::{CodecError, MessageDescriptor};
+use crate::types::ByteAnnotation;
+
+/// SV2 data type of a message field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    U8,
+    U16,
+    U24,
+    U32,
+    U64,
+    F32,
+    Bool,
+    U256,
+    Str0255,
+    B032,
+    B0255,
+    B064K,
+    B016M,
+    Seq0255(&'static FieldType),
+    Seq064K(&'static FieldType),
+    Sv2Option(&'static FieldType),
+}
+
+impl FieldType {
+    /// Name used by the specification, e.g. `SEQ0_255[U256]`.
+    pub fn spec_name(&self) -> String {
+        match self {
+            FieldType::U8 => "U8".to_string(),
+            FieldType::U16 => "U16".to_string(),
+            FieldType::U24 => "U24".to_string(),
+            FieldType::U32 => "U32".to_string(),
+            FieldType::U64 => "U64".to_string(),
+            FieldType::F32 => "F32".to_string(),
+            FieldType::Bool => "BOOL".to_string(),
+            FieldType::U256 => "U256".to_string(),
+            FieldType::Str0255 => "STR0_255".to_string(),
+            FieldType::B032 => "B0_32".to_string(),
+            FieldType::B0255 => "B0_255".to_string(),
+            FieldType::B064K => "B0_64K".to_string(),
+            FieldType::B016M => "B0_16M".to_string(),
+            FieldType::Seq0255(item) => format!("SEQ0_255[{}]", item.spec_name()),
+            FieldType::Seq064K(item) => format!("SEQ0_64K[{}]", item.spec_name()),
+            FieldType::Sv2Option(item) => format!("OPTION[{}]", item.spec_name()),
+        }
+    }
+
+    /// Smallest and largest encoded size in bytes, including length prefixes.
+    pub fn size_range(&self) -> (usize, usize) {
+        match self {
+            FieldType::U8 | FieldType::Bool => (1, 1),
+            FieldType::U16 => (2, 2),
+            FieldType::U24 => (3, 3),
+            FieldType::U32 | FieldType::F32 => (4, 4),
+            FieldType::U64 => (8, 8),
+            FieldType::U256 => (32, 32),
+            FieldType::B032 => (1, 1 + 32),
+            FieldType::Str0255 | FieldType::B0255 => (1, 1 + 255),
+            FieldType::B064K => (2, 2 + 0xffff),
+            FieldType::B016M => (3, 3 + 0xff_ffff),
+            FieldType::Seq0255(item) => (1, 1 + 255 * item.size_range().1),
+            FieldType::Seq064K(item) => (2, 2 + 0xffff * item.size_range().1),
+            FieldType::Sv2Option(item) => (1, 1 + item.size_range().1),
+        }
+    }
+}
+
+impl Serialize for FieldType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.spec_name())
+    }
+}
+
+/// Annotate every byte of an encoded message. With `framed`, `bytes` starts
+/// with the frame header. Bytes after the last field are annotated as TLV
+/// fields where they parse as such.
+pub fn annotate(
+    descriptor: &MessageDescriptor,
+    bytes: &[u8],
+    framed: bool,
+) -> Result<Vec<ByteAnnotation>, CodecError> {
+    let mut cursor = Cursor {
+        bytes,
+        position: 0,
+        annotations: Vec::new(),
+    };
+    if framed {
+        // Bit 15 of extension_type is the channel_msg flag, split out as `frame` does
+        let (bytes, extension_type) = cursor.integer("header.extension_type", 2)?;
+        let extension_type = extension_type as u16;
+        cursor.push(
+            "header.extension_type".to_string(),
+            "U16, low 15 bits".to_string(),
+            bytes,
+            json!(extension_type & !CHANNEL_MSG_BIT),
+        );
+        cursor.push(
+            "header.channel_msg".to_string(),
+            "U16, bit 15".to_string(),
+            &bytes[1..],
+            json!(extension_type & CHANNEL_MSG_BIT != 0),
+        );
+        cursor.walk("header.msg_type".to_string(), FieldType::U8)?;
+        cursor.walk("header.msg_length".to_string(), FieldType::U24)?;
+        debug_assert_eq!(cursor.position, FRAME_HEADER_SIZE);
+    }
+    for (name, field_type) in descriptor.fields {
+        cursor.walk(name.to_string(), *field_type)?;
+    }
+    cursor.trailer();
+    Ok(cursor.annotations)
+}
+
+/// Length of the message fields at the start of `payload`; any bytes after
+/// them are the TLV region.
+pub fn fields_length(descriptor: &MessageDescriptor, payload: &[u8]) -> Result<usize, CodecError> {
+    let mut cursor = Cursor {
+        bytes: payload,
+        position: 0,
+        annotations: Vec::new(),
+    };
+    for (name, field_type) in descriptor.fields {
+        cursor.walk(name.to_string(), *field_type)?;
+    }
+    Ok(cursor.position)
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    annotations: Vec<ByteAnnotation>,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, field: &str, length: usize) -> Result<&'a [u8], CodecError> {
+        let remaining = self.bytes.len() - self.position;
+        if length > remaining {
+            return Err(CodecError::InvalidField {
+                field: field.to_string(),
+                reason: format!(
+                    "needs {} bytes at offset {} but only {} remain",
+                    length, self.position, remaining
+                ),
+            });
+        }
+        let bytes = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn push(&mut self, field: String, data_type: String, bytes: &[u8], value: Value) {
+        self.annotations.push(ByteAnnotation {
+            offset: self.position - bytes.len(),
+            length: bytes.len(),
+            field,
+            data_type,
+            hex: hex::encode(bytes),
+            value,
+        });
+    }
+
+    fn integer(&mut self, field: &str, length: usize) -> Result<(&'a [u8], u64), CodecError> {
+        let bytes = self.take(field, length)?;
+        let value = bytes
+            .iter()
+            .rev()
+            .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+        Ok((bytes, value))
+    }
+
+    /// Length prefix of a variable-size field, annotated as `<field>.length`.
+    fn prefix(&mut self, field: &str, field_type: FieldType, size: usize, label: &str) -> Result<usize, CodecError> {
+        let name = format!("{}.{}", field, label);
+        let (bytes, length) = self.integer(&name, size)?;
+        self.push(name, format!("{} {}", field_type.spec_name(), label), bytes, json!(length));
+        Ok(length as usize)
+    }
+
+    fn walk(&mut self, field: String, field_type: FieldType) -> Result<(), CodecError> {
+        let data_type = field_type.spec_name();
+        match field_type {
+            FieldType::U8 | FieldType::U16 | FieldType::U24 | FieldType::U32 | FieldType::U64 => {
+                let size = match field_type {
+                    FieldType::U8 => 1,
+                    FieldType::U16 => 2,
+                    FieldType::U24 => 3,
+                    FieldType::U32 => 4,
+                    _ => 8,
+                };
+                let (bytes, value) = self.integer(&field, size)?;
+                self.push(field, data_type, bytes, json!(value));
+            }
+            FieldType::F32 => {
+                let bytes = self.take(&field, 4)?;
+                let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                self.push(field, data_type, bytes, f32_json(value));
+            }
+            FieldType::Bool => {
+                let bytes = self.take(&field, 1)?;
+                self.push(field, data_type, bytes, json!(bytes[0] & 1 == 1));
+            }
+            FieldType::U256 => {
+                let bytes = self.take(&field, 32)?;
+                self.push(field, data_type, bytes, json!(hex::encode(bytes)));
+            }
+            FieldType::Str0255 => {
+                let length = self.prefix(&field, field_type, 1, "length")?;
+                let bytes = self.take(&field, length)?;
+                self.push(field, data_type, bytes, json!(String::from_utf8_lossy(bytes)));
+            }
+            FieldType::B032 | FieldType::B0255 | FieldType::B064K | FieldType::B016M => {
+                let size = match field_type {
+                    FieldType::B064K => 2,
+                    FieldType::B016M => 3,
+                    _ => 1,
+                };
+                let length = self.prefix(&field, field_type, size, "length")?;
+                let bytes = self.take(&field, length)?;
+                self.push(field, data_type, bytes, json!(hex::encode(bytes)));
+            }
+            FieldType::Seq0255(item) | FieldType::Seq064K(item) | FieldType::Sv2Option(item) => {
+                let size = match field_type {
+                    FieldType::Seq064K(_) => 2,
+                    _ => 1,
+                };
+                let count = self.prefix(&field, field_type, size, "count")?;
+                for index in 0..count {
+                    self.walk(format!("{}[{}]", field, index), *item)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Annotate bytes after the last field: as many complete TLV fields as
+    /// fit, then whatever is left as an opaque trailer.
+    fn trailer(&mut self) {
+        let mut index = 0;
+        while self.bytes.len() - self.position >= 5 {
+            let start = self.position;
+            let length = u16::from_le_bytes([self.bytes[start + 3], self.bytes[start + 4]]) as usize;
+            if start + 5 + length > self.bytes.len() {
+                break;
+            }
+            let prefix = format!("tlv[{}]", index);
+            let steps = [
+                ("extension_type", FieldType::U16),
+                ("field_type", FieldType::U8),
+                ("length", FieldType::U16),
+            ];
+            for (name, field_type) in steps {
+                self.walk(format!("{}.{}", prefix, name), field_type)
+                    .expect("TLV header fits in the remaining bytes");
+            }
+            let value = self
+                .take(&prefix, length)
+                .expect("TLV value fits in the remaining bytes");
+            self.push(format!("{}.value", prefix), "TLV value".to_string(), value, json!(hex::encode(value)));
+            index += 1;
+        }
+
+        if self.position < self.bytes.len() {
+            let rest = &self.bytes[self.position..];
+            self.position = self.bytes.len();
+            self.push("trailer".to_string(), "unparsed".to_string(), rest, json!(hex::encode(rest)));
+        }
+    }
+}
@@ -0,0 +1,375 @@
+//! Mining protocol messages (`mining_sv2`).
+
+use binary_sv2::U32AsRef;
+use mining_sv2::*;
+use serde_json::{json, Value};
+
+use super::fields::*;
+use super::Direction::*;
+use super::FieldType::*;
+use super::{from_bytes, to_bytes, CodecError, MessageDescriptor, Subprotocol};
+
+pub(super) const MESSAGES: &[MessageDescriptor] = &[
+    MessageDescriptor::new(Subprotocol::Mining, "OpenStandardMiningChannel", MESSAGE_TYPE_OPEN_STANDARD_MINING_CHANNEL, CHANNEL_BIT_OPEN_STANDARD_MINING_CHANNEL, ClientToServer, &[("request_id", U32), ("user_identity", Str0255), ("nominal_hash_rate", F32), ("max_target", U256)]),
+    MessageDescriptor::new(Subprotocol::Mining, "OpenStandardMiningChannelSuccess", MESSAGE_TYPE_OPEN_STANDARD_MINING_CHANNEL_SUCCESS, CHANNEL_BIT_OPEN_STANDARD_MINING_CHANNEL_SUCCESS, ServerToClient, &[("request_id", U32), ("channel_id", U32), ("target", U256), ("extranonce_prefix", B032), ("group_channel_id", U32)]),
+    MessageDescriptor::new(Subprotocol::Mining, "OpenMiningChannelError", MESSAGE_TYPE_OPEN_MINING_CHANNEL_ERROR, CHANNEL_BIT_OPEN_MINING_CHANNEL_ERROR, ServerToClient, &[("request_id", U32), ("error_code", Str0255)]),
+    MessageDescriptor::new(Subprotocol::Mining, "OpenExtendedMiningChannel", MESSAGE_TYPE_OPEN_EXTENDED_MINING_CHANNEL, CHANNEL_BIT_OPEN_EXTENDED_MINING_CHANNEL, ClientToServer, &[("request_id", U32), ("user_identity", Str0255), ("nominal_hash_rate", F32), ("max_target", U256), ("min_extranonce_size", U16)]),
+    MessageDescriptor::new(Subprotocol::Mining, "OpenExtendedMiningChannelSuccess", MESSAGE_TYPE_OPEN_EXTENDED_MINING_CHANNEL_SUCCESS, CHANNEL_BIT_OPEN_EXTENDED_MINING_CHANNEL_SUCCESS, ServerToClient, &[("request_id", U32), ("channel_id", U32), ("target", U256), ("extranonce_size", U16), ("extranonce_prefix", B032)]),
+    MessageDescriptor::new(Subprotocol::Mining, "NewMiningJob", MESSAGE_TYPE_NEW_MINING_JOB, CHANNEL_BIT_NEW_MINING_JOB, ServerToClient, &[("channel_id", U32), ("job_id", U32), ("min_ntime", Sv2Option(&U32)), ("version", U32), ("merkle_root", U256)]),
+    MessageDescriptor::new(Subprotocol::Mining, "UpdateChannel", MESSAGE_TYPE_UPDATE_CHANNEL, CHANNEL_BIT_UPDATE_CHANNEL, ClientToServer, &[("channel_id", U32), ("nominal_hash_rate", F32), ("maximum_target", U256)]),
+    MessageDescriptor::new(Subprotocol::Mining, "UpdateChannelError", MESSAGE_TYPE_UPDATE_CHANNEL_ERROR, CHANNEL_BIT_UPDATE_CHANNEL_ERROR, ServerToClient, &[("channel_id", U32), ("error_code", Str0255)]),
+    MessageDescriptor::new(Subprotocol::Mining, "CloseChannel", MESSAGE_TYPE_CLOSE_CHANNEL, CHANNEL_BIT_CLOSE_CHANNEL, Both, &[("channel_id", U32), ("reason_code", Str0255)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SetExtranoncePrefix", MESSAGE_TYPE_SET_EXTRANONCE_PREFIX, CHANNEL_BIT_SET_EXTRANONCE_PREFIX, ServerToClient, &[("channel_id", U32), ("extranonce_prefix", B032)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SubmitSharesStandard", MESSAGE_TYPE_SUBMIT_SHARES_STANDARD, CHANNEL_BIT_SUBMIT_SHARES_STANDARD, ClientToServer, &[("channel_id", U32), ("sequence_number", U32), ("job_id", U32), ("nonce", U32), ("ntime", U32), ("version", U32)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SubmitSharesExtended", MESSAGE_TYPE_SUBMIT_SHARES_EXTENDED, CHANNEL_BIT_SUBMIT_SHARES_EXTENDED, ClientToServer, &[("channel_id", U32), ("sequence_number", U32), ("job_id", U32), ("nonce", U32), ("ntime", U32), ("version", U32), ("extranonce", B032)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SubmitSharesSuccess", MESSAGE_TYPE_SUBMIT_SHARES_SUCCESS, CHANNEL_BIT_SUBMIT_SHARES_SUCCESS, ServerToClient, &[("channel_id", U32), ("last_sequence_number", U32), ("new_submits_accepted_count", U32), ("new_shares_sum", U64)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SubmitSharesError", MESSAGE_TYPE_SUBMIT_SHARES_ERROR, CHANNEL_BIT_SUBMIT_SHARES_ERROR, ServerToClient, &[("channel_id", U32), ("sequence_number", U32), ("error_code", Str0255)]),
+    MessageDescriptor::new(Subprotocol::Mining, "NewExtendedMiningJob", MESSAGE_TYPE_NEW_EXTENDED_MINING_JOB, CHANNEL_BIT_NEW_EXTENDED_MINING_JOB, ServerToClient, &[("channel_id", U32), ("job_id", U32), ("min_ntime", Sv2Option(&U32)), ("version", U32), ("version_rolling_allowed", Bool), ("merkle_path", Seq0255(&U256)), ("coinbase_tx_prefix", B064K), ("coinbase_tx_suffix", B064K)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SetNewPrevHash", MESSAGE_TYPE_MINING_SET_NEW_PREV_HASH, CHANNEL_BIT_MINING_SET_NEW_PREV_HASH, ServerToClient, &[("channel_id", U32), ("job_id", U32), ("prev_hash", U256), ("min_ntime", U32), ("nbits", U32)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SetTarget", MESSAGE_TYPE_SET_TARGET, CHANNEL_BIT_SET_TARGET, ServerToClient, &[("channel_id", U32), ("maximum_target", U256)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SetCustomMiningJob", MESSAGE_TYPE_SET_CUSTOM_MINING_JOB, CHANNEL_BIT_SET_CUSTOM_MINING_JOB, ClientToServer, &[("channel_id", U32), ("request_id", U32), ("token", B0255), ("version", U32), ("prev_hash", U256), ("min_ntime", U32), ("nbits", U32), ("coinbase_tx_version", U32), ("coinbase_prefix", B0255), ("coinbase_tx_input_n_sequence", U32), ("coinbase_tx_outputs", B064K), ("coinbase_tx_locktime", U32), ("merkle_path", Seq0255(&U256))]),
+    MessageDescriptor::new(Subprotocol::Mining, "SetCustomMiningJobSuccess", MESSAGE_TYPE_SET_CUSTOM_MINING_JOB_SUCCESS, CHANNEL_BIT_SET_CUSTOM_MINING_JOB_SUCCESS, ServerToClient, &[("channel_id", U32), ("request_id", U32), ("job_id", U32)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SetCustomMiningJobError", MESSAGE_TYPE_SET_CUSTOM_MINING_JOB_ERROR, CHANNEL_BIT_SET_CUSTOM_MINING_JOB_ERROR, ServerToClient, &[("channel_id", U32), ("request_id", U32), ("error_code", Str0255)]),
+    MessageDescriptor::new(Subprotocol::Mining, "SetGroupChannel", MESSAGE_TYPE_SET_GROUP_CHANNEL, CHANNEL_BIT_SET_GROUP_CHANNEL, ServerToClient, &[("group_channel_id", U32), ("channel_ids", Seq064K(&U32))]),
+];
+
+pub(super) fn encode(name: &str, message: &Value) -> Result<Vec<u8>, CodecError> {
+    match name {
+        "OpenStandardMiningChannel" => to_bytes(OpenStandardMiningChannel {
+            request_id: U32AsRef::from(u32_field(message, "request_id")?),
+            user_identity: str0255_field(message, "user_identity")?,
+            nominal_hash_rate: f32_field(message, "nominal_hash_rate")?,
+            max_target: bytes_field(message, "max_target", "U256")?,
+        }),
+        "OpenStandardMiningChannelSuccess" => to_bytes(OpenStandardMiningChannelSuccess {
+            request_id: U32AsRef::from(u32_field(message, "request_id")?),
+            channel_id: u32_field(message, "channel_id")?,
+            target: bytes_field(message, "target", "U256")?,
+            extranonce_prefix: bytes_field(message, "extranonce_prefix", "B0_32")?,
+            group_channel_id: u32_field(message, "group_channel_id")?,
+        }),
+        "OpenMiningChannelError" => to_bytes(OpenMiningChannelError {
+            request_id: u32_field(message, "request_id")?,
+            error_code: str0255_field(message, "error_code")?,
+        }),
+        "OpenExtendedMiningChannel" => to_bytes(OpenExtendedMiningChannel {
+            request_id: u32_field(message, "request_id")?,
+            user_identity: str0255_field(message, "user_identity")?,
+            nominal_hash_rate: f32_field(message, "nominal_hash_rate")?,
+            max_target: bytes_field(message, "max_target", "U256")?,
+            min_extranonce_size: u16_field(message, "min_extranonce_size")?,
+        }),
+        "OpenExtendedMiningChannelSuccess" => to_bytes(OpenExtendedMiningChannelSuccess {
+            request_id: u32_field(message, "request_id")?,
+            channel_id: u32_field(message, "channel_id")?,
+            target: bytes_field(message, "target", "U256")?,
+            extranonce_size: u16_field(message, "extranonce_size")?,
+            extranonce_prefix: bytes_field(message, "extranonce_prefix", "B0_32")?,
+        }),
+        "NewMiningJob" => to_bytes(NewMiningJob {
+            channel_id: u32_field(message, "channel_id")?,
+            job_id: u32_field(message, "job_id")?,
+            min_ntime: option_u32_field(message, "min_ntime")?,
+            version: u32_field(message, "version")?,
+            merkle_root: bytes_field(message, "merkle_root", "U256")?,
+        }),
+        "UpdateChannel" => to_bytes(UpdateChannel {
+            channel_id: u32_field(message, "channel_id")?,
+            nominal_hash_rate: f32_field(message, "nominal_hash_rate")?,
+            maximum_target: bytes_field(message, "maximum_target", "U256")?,
+        }),
+        "UpdateChannelError" => to_bytes(UpdateChannelError {
+            channel_id: u32_field(message, "channel_id")?,
+            error_code: str0255_field(message, "error_code")?,
+        }),
+        "CloseChannel" => to_bytes(CloseChannel {
+            channel_id: u32_field(message, "channel_id")?,
+            reason_code: str0255_field(message, "reason_code")?,
+        }),
+        "SetExtranoncePrefix" => to_bytes(SetExtranoncePrefix {
+            channel_id: u32_field(message, "channel_id")?,
+            extranonce_prefix: bytes_field(message, "extranonce_prefix", "B0_32")?,
+        }),
+        "SubmitSharesStandard" => to_bytes(SubmitSharesStandard {
+            channel_id: u32_field(message, "channel_id")?,
+            sequence_number: u32_field(message, "sequence_number")?,
+            job_id: u32_field(message, "job_id")?,
+            nonce: u32_field(message, "nonce")?,
+            ntime: u32_field(message, "ntime")?,
+            version: u32_field(message, "version")?,
+        }),
+        "SubmitSharesExtended" => to_bytes(SubmitSharesExtended {
+            channel_id: u32_field(message, "channel_id")?,
+            sequence_number: u32_field(message, "sequence_number")?,
+            job_id: u32_field(message, "job_id")?,
+            nonce: u32_field(message, "nonce")?,
+            ntime: u32_field(message, "ntime")?,
+            version: u32_field(message, "version")?,
+            extranonce: bytes_field(message, "extranonce", "B0_32")?,
+        }),
+        "SubmitSharesSuccess" => to_bytes(SubmitSharesSuccess {
+            channel_id: u32_field(message, "channel_id")?,
+            last_sequence_number: u32_field(message, "last_sequence_number")?,
+            new_submits_accepted_count: u32_field(message, "new_submits_accepted_count")?,
+            new_shares_sum: u64_field(message, "new_shares_sum")?,
+        }),
+        "SubmitSharesError" => to_bytes(SubmitSharesError {
+            channel_id: u32_field(message, "channel_id")?,
+            sequence_number: u32_field(message, "sequence_number")?,
+            error_code: str0255_field(message, "error_code")?,
+        }),
+        "NewExtendedMiningJob" => to_bytes(NewExtendedMiningJob {
+            channel_id: u32_field(message, "channel_id")?,
+            job_id: u32_field(message, "job_id")?,
+            min_ntime: option_u32_field(message, "min_ntime")?,
+            version: u32_field(message, "version")?,
+            version_rolling_allowed: bool_field(message, "version_rolling_allowed")?,
+            merkle_path: empty_seq0255(),
+            coinbase_tx_prefix: bytes_field(message, "coinbase_tx_prefix", "B0_64K")?,
+            coinbase_tx_suffix: bytes_field(message, "coinbase_tx_suffix", "B0_64K")?,
+        }),
+        "SetNewPrevHash" => to_bytes(SetNewPrevHash {
+            channel_id: u32_field(message, "channel_id")?,
+            job_id: u32_field(message, "job_id")?,
+            prev_hash: bytes_field(message, "prev_hash", "U256")?,
+            min_ntime: u32_field(message, "min_ntime")?,
+            nbits: u32_field(message, "nbits")?,
+        }),
+        "SetTarget" => to_bytes(SetTarget {
+            channel_id: u32_field(message, "channel_id")?,
+            maximum_target: bytes_field(message, "maximum_target", "U256")?,
+        }),
+        "SetCustomMiningJob" => to_bytes(SetCustomMiningJob {
+            channel_id: u32_field(message, "channel_id")?,
+            request_id: u32_field(message, "request_id")?,
+            token: bytes_field(message, "token", "B0_255")?,
+            version: u32_field(message, "version")?,
+            prev_hash: bytes_field(message, "prev_hash", "U256")?,
+            min_ntime: u32_field(message, "min_ntime")?,
+            nbits: u32_field(message, "nbits")?,
+            coinbase_tx_version: u32_field(message, "coinbase_tx_version")?,
+            coinbase_prefix: bytes_field(message, "coinbase_prefix", "B0_255")?,
+            coinbase_tx_input_n_sequence: u32_field(message, "coinbase_tx_input_n_sequence")?,
+            coinbase_tx_outputs: bytes_field(message, "coinbase_tx_outputs", "B0_64K")?,
+            coinbase_tx_locktime: u32_field(message, "coinbase_tx_locktime")?,
+            merkle_path: empty_seq0255(),
+        }),
+        "SetCustomMiningJobSuccess" => to_bytes(SetCustomMiningJobSuccess {
+            channel_id: u32_field(message, "channel_id")?,
+            request_id: u32_field(message, "request_id")?,
+            job_id: u32_field(message, "job_id")?,
+        }),
+        "SetCustomMiningJobError" => to_bytes(SetCustomMiningJobError {
+            channel_id: u32_field(message, "channel_id")?,
+            request_id: u32_field(message, "request_id")?,
+            error_code: str0255_field(message, "error_code")?,
+        }),
+        "SetGroupChannel" => to_bytes(SetGroupChannel {
+            group_channel_id: u32_field(message, "group_channel_id")?,
+            channel_ids: empty_seq064k(),
+        }),
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
+
+pub(super) fn decode(name: &str, payload: &mut [u8]) -> Result<Value, CodecError> {
+    match name {
+        "OpenStandardMiningChannel" => {
+            let m: OpenStandardMiningChannel = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id.as_u32(),
+                "user_identity": str_json("user_identity", &m.user_identity)?,
+                "nominal_hash_rate": f32_json(m.nominal_hash_rate),
+                "max_target": hex_json(m.max_target.inner_as_ref()),
+            }))
+        }
+        "OpenStandardMiningChannelSuccess" => {
+            let m: OpenStandardMiningChannelSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id.as_u32(),
+                "channel_id": m.channel_id,
+                "target": hex_json(m.target.inner_as_ref()),
+                "extranonce_prefix": hex_json(m.extranonce_prefix.inner_as_ref()),
+                "group_channel_id": m.group_channel_id,
+            }))
+        }
+        "OpenMiningChannelError" => {
+            let m: OpenMiningChannelError = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "error_code": str_json("error_code", &m.error_code)?,
+            }))
+        }
+        "OpenExtendedMiningChannel" => {
+            let m: OpenExtendedMiningChannel = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "user_identity": str_json("user_identity", &m.user_identity)?,
+                "nominal_hash_rate": f32_json(m.nominal_hash_rate),
+                "max_target": hex_json(m.max_target.inner_as_ref()),
+                "min_extranonce_size": m.min_extranonce_size,
+            }))
+        }
+        "OpenExtendedMiningChannelSuccess" => {
+            let m: OpenExtendedMiningChannelSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "request_id": m.request_id,
+                "channel_id": m.channel_id,
+                "target": hex_json(m.target.inner_as_ref()),
+                "extranonce_size": m.extranonce_size,
+                "extranonce_prefix": hex_json(m.extranonce_prefix.inner_as_ref()),
+            }))
+        }
+        "NewMiningJob" => {
+            let m: NewMiningJob = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "job_id": m.job_id,
+                "min_ntime": m.min_ntime.into_inner(),
+                "version": m.version,
+                "merkle_root": hex_json(m.merkle_root.inner_as_ref()),
+            }))
+        }
+        "UpdateChannel" => {
+            let m: UpdateChannel = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "nominal_hash_rate": f32_json(m.nominal_hash_rate),
+                "maximum_target": hex_json(m.maximum_target.inner_as_ref()),
+            }))
+        }
+        "UpdateChannelError" => {
+            let m: UpdateChannelError = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "error_code": str_json("error_code", &m.error_code)?,
+            }))
+        }
+        "CloseChannel" => {
+            let m: CloseChannel = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "reason_code": str_json("reason_code", &m.reason_code)?,
+            }))
+        }
+        "SetExtranoncePrefix" => {
+            let m: SetExtranoncePrefix = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "extranonce_prefix": hex_json(m.extranonce_prefix.inner_as_ref()),
+            }))
+        }
+        "SubmitSharesStandard" => {
+            let m: SubmitSharesStandard = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "sequence_number": m.sequence_number,
+                "job_id": m.job_id,
+                "nonce": m.nonce,
+                "ntime": m.ntime,
+                "version": m.version,
+            }))
+        }
+        "SubmitSharesExtended" => {
+            let m: SubmitSharesExtended = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "sequence_number": m.sequence_number,
+                "job_id": m.job_id,
+                "nonce": m.nonce,
+                "ntime": m.ntime,
+                "version": m.version,
+                "extranonce": hex_json(m.extranonce.inner_as_ref()),
+            }))
+        }
+        "SubmitSharesSuccess" => {
+            let m: SubmitSharesSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "last_sequence_number": m.last_sequence_number,
+                "new_submits_accepted_count": m.new_submits_accepted_count,
+                "new_shares_sum": m.new_shares_sum,
+            }))
+        }
+        "SubmitSharesError" => {
+            let m: SubmitSharesError = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "sequence_number": m.sequence_number,
+                "error_code": str_json("error_code", &m.error_code)?,
+            }))
+        }
+        "NewExtendedMiningJob" => {
+            let m: NewExtendedMiningJob = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "job_id": m.job_id,
+                "min_ntime": m.min_ntime.into_inner(),
+                "version": m.version,
+                "version_rolling_allowed": m.version_rolling_allowed,
+                "merkle_path": hex_list_json(m.merkle_path.to_vec()),
+                "coinbase_tx_prefix": hex_json(m.coinbase_tx_prefix.inner_as_ref()),
+                "coinbase_tx_suffix": hex_json(m.coinbase_tx_suffix.inner_as_ref()),
+            }))
+        }
+        "SetNewPrevHash" => {
+            let m: SetNewPrevHash = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "job_id": m.job_id,
+                "prev_hash": hex_json(m.prev_hash.inner_as_ref()),
+                "min_ntime": m.min_ntime,
+                "nbits": m.nbits,
+            }))
+        }
+        "SetTarget" => {
+            let m: SetTarget = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "maximum_target": hex_json(m.maximum_target.inner_as_ref()),
+            }))
+        }
+        "SetCustomMiningJob" => {
+            let m: SetCustomMiningJob = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "request_id": m.request_id,
+                "token": hex_json(m.token.inner_as_ref()),
+                "version": m.version,
+                "prev_hash": hex_json(m.prev_hash.inner_as_ref()),
+                "min_ntime": m.min_ntime,
+                "nbits": m.nbits,
+                "coinbase_tx_version": m.coinbase_tx_version,
+                "coinbase_prefix": hex_json(m.coinbase_prefix.inner_as_ref()),
+                "coinbase_tx_input_n_sequence": m.coinbase_tx_input_n_sequence,
+                "coinbase_tx_outputs": hex_json(m.coinbase_tx_outputs.inner_as_ref()),
+                "coinbase_tx_locktime": m.coinbase_tx_locktime,
+                "merkle_path": hex_list_json(m.merkle_path.to_vec()),
+            }))
+        }
+        "SetCustomMiningJobSuccess" => {
+            let m: SetCustomMiningJobSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "request_id": m.request_id,
+                "job_id": m.job_id,
+            }))
+        }
+        "SetCustomMiningJobError" => {
+            let m: SetCustomMiningJobError = from_bytes(payload)?;
+            Ok(json!({
+                "channel_id": m.channel_id,
+                "request_id": m.request_id,
+                "error_code": str_json("error_code", &m.error_code)?,
+            }))
+        }
+        "SetGroupChannel" => {
+            let m: SetGroupChannel = from_bytes(payload)?;
+            Ok(json!({
+                "group_channel_id": m.group_channel_id,
+                "channel_ids": m.channel_ids.into_inner(),
+            }))
+        }
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
@@ -0,0 +1,296 @@
+//! JSON <-> binary conversion for Stratum V2 messages, built on the official
+//! subprotocol crates and `binary_codec_sv2`.
+
+use serde::Serialize;
+use serde_json::Value;
+
+mod common;
+mod fields;
+mod frame;
+mod job_declaration;
+mod layout;
+mod mining;
+mod sample;
+mod schema;
+mod template_distribution;
+mod vectors;
+mod verify;
+
+pub use frame::{decode_frame, encode_frame, frame_message_type, FRAME_HEADER_SIZE};
+pub use layout::{annotate, fields_length, FieldType};
+pub use sample::{default_message, random_message};
+pub use schema::json_schema;
+pub use vectors::test_vectors;
+pub use verify::round_trip_mismatches;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CodecError {
+    #[error("Unknown message type: {0}")]
+    UnknownMessageType(String),
+    #[error("Ambiguous message type {name}: use one of {}", .candidates.join(", "))]
+    AmbiguousMessageType { name: String, candidates: Vec<String> },
+    #[error("Missing field: {0}")]
+    MissingField(String),
+    #[error("Invalid value for field {field}: {reason}")]
+    InvalidField { field: String, reason: String },
+    #[error("Invalid frame: {0}")]
+    InvalidFrame(String),
+    #[error("Binary codec error: {0:?}")]
+    Binary(binary_sv2::Error),
+}
+
+impl From<binary_sv2::Error> for CodecError {
+    fn from(error: binary_sv2::Error) -> Self {
+        CodecError::Binary(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subprotocol {
+    Common,
+    Mining,
+    JobDeclaration,
+    TemplateDistribution,
+}
+
+impl Subprotocol {
+    /// Prefix used to qualify message names shared between subprotocols,
+    /// e.g. `TemplateDistribution::SetNewPrevHash`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Subprotocol::Common => "Common",
+            Subprotocol::Mining => "Mining",
+            Subprotocol::JobDeclaration => "JobDeclaration",
+            Subprotocol::TemplateDistribution => "TemplateDistribution",
+        }
+    }
+}
+
+/// Which side of a connection sends a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    ClientToServer,
+    ServerToClient,
+    Both,
+}
+
+impl Direction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Direction::ClientToServer => "Client -> Server",
+            Direction::ServerToClient => "Server -> Client",
+            Direction::Both => "Client <-> Server",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageDescriptor {
+    pub name: &'static str,
+    pub subprotocol: Subprotocol,
+    pub msg_type: u8,
+    pub channel_msg: bool,
+    pub direction: Direction,
+    /// Field names and types in wire order.
+    pub fields: &'static [(&'static str, FieldType)],
+}
+
+impl MessageDescriptor {
+    pub const fn new(
+        subprotocol: Subprotocol,
+        name: &'static str,
+        msg_type: u8,
+        channel_msg: bool,
+        direction: Direction,
+        fields: &'static [(&'static str, FieldType)],
+    ) -> Self {
+        MessageDescriptor {
+            name,
+            subprotocol,
+            msg_type,
+            channel_msg,
+            direction,
+            fields,
+        }
+    }
+
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.subprotocol.label(), self.name)
+    }
+
+    /// Name that [`lookup`] resolves back to this message: qualified only when
+    /// another subprotocol defines a message of the same name.
+    pub fn lookup_name(&self) -> String {
+        if messages().filter(|other| other.name == self.name).count() > 1 {
+            self.qualified_name()
+        } else {
+            self.name.to_string()
+        }
+    }
+}
+
+/// Every message the codec can encode, in wire order within each subprotocol.
+pub fn messages() -> impl Iterator<Item = &'static MessageDescriptor> {
+    common::MESSAGES
+        .iter()
+        .chain(mining::MESSAGES)
+        .chain(job_declaration::MESSAGES)
+        .chain(template_distribution::MESSAGES)
+}
+
+/// Names used by earlier releases of this server for messages the crates define
+/// under a different name.
+const ALIASES: &[(&str, &str)] = &[
+    ("OpenStandardMiningChannelError", "OpenMiningChannelError"),
+    ("OpenExtendedMiningChannelError", "OpenMiningChannelError"),
+];
+
+/// Resolve a message name, accepting both `SetupConnectionSuccess` and the
+/// spec's dotted `SetupConnection.Success` spelling. Names defined by more than
+/// one subprotocol must be qualified, e.g. `Mining::SetNewPrevHash`.
+pub fn lookup(message_type: &str) -> Result<&'static MessageDescriptor, CodecError> {
+    let (subprotocol, name) = match message_type.split_once("::") {
+        Some((prefix, name)) => (Some(prefix.replace('_', "").to_lowercase()), name),
+        None => (None, message_type),
+    };
+    let mut name = name.replace('.', "");
+    if let Some((_, canonical)) = ALIASES.iter().find(|(alias, _)| *alias == name) {
+        name = canonical.to_string();
+    }
+
+    let candidates: Vec<&'static MessageDescriptor> = messages()
+        .filter(|descriptor| descriptor.name == name)
+        .filter(|descriptor| match &subprotocol {
+            Some(prefix) => descriptor.subprotocol.label().to_lowercase() == *prefix,
+            None => true,
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [descriptor] => Ok(descriptor),
+        [] => Err(CodecError::UnknownMessageType(message_type.to_string())),
+        _ => Err(CodecError::AmbiguousMessageType {
+            name: message_type.to_string(),
+            candidates: candidates.iter().map(|d| d.qualified_name()).collect(),
+        }),
+    }
+}
+
+/// Find the message a frame header refers to. Only the core protocol
+/// (extension_type 0) defines messages the codec knows about.
+pub fn lookup_by_type(extension_type: u16, msg_type: u8) -> Result<&'static MessageDescriptor, CodecError> {
+    messages()
+        .find(|descriptor| extension_type == 0 && descriptor.msg_type == msg_type)
+        .ok_or_else(|| {
+            CodecError::UnknownMessageType(format!(
+                "msg_type 0x{:02x} in extension 0x{:04x}",
+                msg_type, extension_type
+            ))
+        })
+}
+
+/// Encode a JSON message into its binary SV2 payload (without frame header).
+pub fn encode(message_type: &str, message: &Value) -> Result<Vec<u8>, CodecError> {
+    let descriptor = lookup(message_type)?;
+    // `binary_codec_sv2` overflows sizing a SEQ0_255 of 255 items or a SEQ0_64K
+    // of 65534 or more, so sequences are encoded empty and written here.
+    let sequences = descriptor
+        .fields
+        .iter()
+        .filter(|(_, field_type)| matches!(field_type, FieldType::Seq0255(_) | FieldType::Seq064K(_)))
+        .map(|(name, field_type)| Ok((*name, fields::sequence_bytes(message, name, *field_type)?)))
+        .collect::<Result<Vec<_>, CodecError>>()?;
+    let mut payload = match descriptor.subprotocol {
+        Subprotocol::Common => common::encode(descriptor.name, message),
+        Subprotocol::Mining => mining::encode(descriptor.name, message),
+        Subprotocol::JobDeclaration => job_declaration::encode(descriptor.name, message),
+        Subprotocol::TemplateDistribution => template_distribution::encode(descriptor.name, message),
+    }?;
+    if sequences.is_empty() {
+        return Ok(payload);
+    }
+    let annotations = annotate(descriptor, &payload, false)?;
+    // Splice from the last field back so earlier offsets stay valid.
+    for (name, bytes) in sequences.into_iter().rev() {
+        let count = format!("{}.count", name);
+        let prefix = annotations
+            .iter()
+            .find(|annotation| annotation.field == count)
+            .expect("every sequence has a count prefix");
+        payload.splice(prefix.offset..prefix.offset + prefix.length, bytes);
+    }
+    Ok(payload)
+}
+
+/// Decode a binary SV2 payload (without frame header) into a JSON object with
+/// named fields.
+pub fn decode(message_type: &str, payload: &[u8]) -> Result<Value, CodecError> {
+    decode_as(lookup(message_type)?, payload)
+}
+
+/// Decode a complete frame, detecting the message from its header.
+pub fn decode_frame_message(bytes: &[u8]) -> Result<(&'static MessageDescriptor, Value), CodecError> {
+    let (header, payload) = decode_frame(bytes)?;
+    if header.msg_length as usize != payload.len() {
+        return Err(CodecError::InvalidFrame(format!(
+            "msg_length declares {} bytes but {} follow the header",
+            header.msg_length,
+            payload.len()
+        )));
+    }
+    let descriptor = lookup_by_type(header.extension_type, header.msg_type)?;
+    Ok((descriptor, decode_as(descriptor, payload)?))
+}
+
+fn decode_as(descriptor: &MessageDescriptor, payload: &[u8]) -> Result<Value, CodecError> {
+    let mut payload = payload.to_vec();
+    match descriptor.subprotocol {
+        Subprotocol::Common => common::decode(descriptor.name, &mut payload),
+        Subprotocol::Mining => mining::decode(descriptor.name, &mut payload),
+        Subprotocol::JobDeclaration => job_declaration::decode(descriptor.name, &mut payload),
+        Subprotocol::TemplateDistribution => template_distribution::decode(descriptor.name, &mut payload),
+    }
+}
+
+fn to_bytes<T>(message: T) -> Result<Vec<u8>, CodecError>
+where
+    T: binary_sv2::Serialize + binary_sv2::GetSize,
+{
+    Ok(binary_sv2::to_bytes(message)?)
+}
+
+fn from_bytes<'a, T>(payload: &'a mut [u8]) -> Result<T, CodecError>
+where
+    T: binary_sv2::Deserialize<'a>,
+{
+    Ok(binary_sv2::from_bytes(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn every_message_round_trips() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for descriptor in messages() {
+            let name = descriptor.lookup_name();
+            for message in [default_message(descriptor), random_message(descriptor, &mut rng)] {
+                let payload = encode(&name, &message).unwrap_or_else(|e| panic!("{}: {}", name, e));
+                let decoded = decode(&name, &payload).unwrap_or_else(|e| panic!("{}: {}", name, e));
+                assert!(round_trip_mismatches(descriptor, &message, &decoded).is_empty(), "{}", name);
+                assert_eq!(fields_length(descriptor, &payload).unwrap(), payload.len(), "{}", name);
+
+                // An empty TLV field after the fields is not part of them
+                let mut with_tlv = payload.clone();
+                with_tlv.extend_from_slice(&[0x00; 5]);
+                assert_eq!(fields_length(descriptor, &with_tlv).unwrap(), payload.len(), "{}", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Sample messages built from a message's field layout, either with the
+//! smallest valid value for every field or with random values.
+
+use rand::Rng;
+use serde_json::{json, Map, Value};
+
+use super::common::integer_field_max;
+use super::fields::f32_json;
+use super::{FieldType, MessageDescriptor};
+
+/// Upper bound on random binary and string lengths, so B0_16M fields stay readable.
+const RANDOM_MAX_BYTES: usize = 64;
+/// Upper bound on the number of random sequence items.
+const RANDOM_MAX_ITEMS: usize = 8;
+
+/// Message with every field set to zero, false, empty or null.
+pub fn default_message(descriptor: &MessageDescriptor) -> Value {
+    let fields = descriptor
+        .fields
+        .iter()
+        .map(|(name, field_type)| (name.to_string(), default_value(*field_type)))
+        .collect::<Map<_, _>>();
+    Value::Object(fields)
+}
+
+/// Message with random values that the encoder accepts: integers span their
+/// whole range, binary and string lengths and sequence sizes are capped.
+pub fn random_message(descriptor: &MessageDescriptor, rng: &mut impl Rng) -> Value {
+    let fields = descriptor
+        .fields
+        .iter()
+        .map(|(name, field_type)| {
+            let value = match integer_field_max(descriptor, name) {
+                Some(max) => json!(rng.gen_range(0..=max)),
+                None => random_value(*field_type, rng),
+            };
+            (name.to_string(), value)
+        })
+        .collect::<Map<_, _>>();
+    Value::Object(fields)
+}
+
+pub(super) fn default_value(field_type: FieldType) -> Value {
+    match field_type {
+        FieldType::U8 | FieldType::U16 | FieldType::U24 | FieldType::U32 | FieldType::U64 => json!(0),
+        FieldType::F32 => json!(0.0),
+        FieldType::Bool => json!(false),
+        FieldType::U256 => json!(hex::encode([0u8; 32])),
+        FieldType::Str0255 | FieldType::B032 | FieldType::B0255 | FieldType::B064K | FieldType::B016M => json!(""),
+        FieldType::Seq0255(_) | FieldType::Seq064K(_) => json!([]),
+        FieldType::Sv2Option(_) => Value::Null,
+    }
+}
+
+fn random_value(field_type: FieldType, rng: &mut impl Rng) -> Value {
+    match field_type {
+        FieldType::U8 => json!(rng.gen::<u8>()),
+        FieldType::U16 => json!(rng.gen::<u16>()),
+        FieldType::U24 => json!(rng.gen_range(0..=0xff_ffffu32)),
+        FieldType::U32 => json!(rng.gen::<u32>()),
+        FieldType::U64 => json!(rng.gen::<u64>()),
+        FieldType::F32 => f32_json(rng.gen::<f32>() * f32::MAX),
+        FieldType::Bool => json!(rng.gen::<bool>()),
+        FieldType::U256 => json!(hex::encode(random_bytes(32, rng))),
+        FieldType::Str0255 => {
+            let length = rng.gen_range(0..=RANDOM_MAX_BYTES);
+            let text: String = (0..length).map(|_| rng.sample(rand::distributions::Alphanumeric) as char).collect();
+            json!(text)
+        }
+        FieldType::B032 | FieldType::B0255 | FieldType::B064K | FieldType::B016M => {
+            let max = match field_type {
+                FieldType::B032 => 32,
+                _ => RANDOM_MAX_BYTES,
+            };
+            let length = rng.gen_range(0..=max);
+            json!(hex::encode(random_bytes(length, rng)))
+        }
+        FieldType::Seq0255(item) | FieldType::Seq064K(item) => {
+            let count = rng.gen_range(0..=RANDOM_MAX_ITEMS);
+            Value::Array((0..count).map(|_| random_value(*item, rng)).collect())
+        }
+        FieldType::Sv2Option(item) => match rng.gen::<bool>() {
+            true => random_value(*item, rng),
+            false => Value::Null,
+        },
+    }
+}
+
+fn random_bytes(length: usize, rng: &mut impl Rng) -> Vec<u8> {
+    (0..length).map(|_| rng.gen()).collect()
+}
@@ -0,0 +1,171 @@
+//! JSON Schema for the JSON form of a message, as accepted by [`super::encode`].
+
+use serde_json::{json, Map, Value};
+
+use super::common::{integer_field_max, PROTOCOL_NAMES};
+use super::{FieldType, MessageDescriptor, Subprotocol};
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Schema of the JSON object `encode` accepts for `descriptor`. Fields other
+/// than OPTION fields are required; unknown properties are ignored by the
+/// encoder and therefore allowed.
+pub fn json_schema(descriptor: &MessageDescriptor) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for (name, field_type) in descriptor.fields {
+        let mut schema = field_schema(*field_type);
+        if let Some(maximum) = integer_field_max(descriptor, name) {
+            schema["anyOf"][0]["maximum"] = json!(maximum);
+            // Single-digit limits can be expressed for the string forms too
+            if maximum < 10 {
+                schema["anyOf"][1]["pattern"] = json!(format!("^(0x0*[0-{0}]|0*[0-{0}])$", maximum));
+            }
+        }
+        // SetupConnection's protocol also accepts subprotocol names
+        if descriptor.subprotocol == Subprotocol::Common && *name == "protocol" {
+            let names: Vec<&str> = PROTOCOL_NAMES.iter().map(|(name, _)| *name).collect();
+            schema["anyOf"].as_array_mut().unwrap().push(json!({ "enum": names }));
+        }
+        schema["description"] = json!(field_type.spec_name());
+        properties.insert(name.to_string(), schema);
+        if !matches!(field_type, FieldType::Sv2Option(_)) {
+            required.push(json!(name));
+        }
+    }
+
+    json!({
+        "$schema": DRAFT,
+        "title": descriptor.lookup_name(),
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn field_schema(field_type: FieldType) -> Value {
+    match field_type {
+        FieldType::U8 => integer_schema(u8::MAX as u64, 2),
+        FieldType::U16 => integer_schema(u16::MAX as u64, 4),
+        FieldType::U24 => integer_schema(0xff_ffff, 6),
+        FieldType::U32 => integer_schema(u32::MAX as u64, 8),
+        FieldType::U64 => integer_schema(u64::MAX, 16),
+        FieldType::F32 => json!({ "type": "number" }),
+        FieldType::Bool => json!({ "type": "boolean" }),
+        // maxLength counts characters, the encoder limit is 255 bytes of UTF-8
+        FieldType::Str0255 => json!({ "type": "string", "maxLength": 255 }),
+        FieldType::U256 => json!({ "type": "string", "pattern": "^(0x)?[0-9a-fA-F]{64}$" }),
+        FieldType::B032 => bytes_schema(32),
+        FieldType::B0255 => bytes_schema(255),
+        FieldType::B064K => bytes_schema(0xffff),
+        FieldType::B016M => bytes_schema(0xff_ffff),
+        FieldType::Seq0255(item) => json!({ "type": "array", "items": field_schema(*item), "maxItems": 255 }),
+        FieldType::Seq064K(item) => json!({ "type": "array", "items": field_schema(*item), "maxItems": 0xffff }),
+        FieldType::Sv2Option(item) => json!({ "anyOf": [field_schema(*item), { "type": "null" }] }),
+    }
+}
+
+/// Integers are accepted as JSON numbers, decimal strings or `0x` hex strings.
+fn integer_schema(maximum: u64, hex_digits: usize) -> Value {
+    json!({
+        "anyOf": [
+            { "type": "integer", "minimum": 0, "maximum": maximum },
+            { "type": "string", "pattern": format!("^(0x[0-9a-fA-F]{{1,{}}}|[0-9]+)$", hex_digits) },
+        ]
+    })
+}
+
+/// Hex string of at most `max_bytes` bytes, optionally `0x`-prefixed.
+fn bytes_schema(max_bytes: usize) -> Value {
+    json!({
+        "anyOf": [
+            { "type": "string", "pattern": "^([0-9a-fA-F]{2})*$", "maxLength": 2 * max_bytes },
+            { "type": "string", "pattern": "^0x([0-9a-fA-F]{2})*$", "maxLength": 2 + 2 * max_bytes },
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::super::sample::{default_value, random_message};
+    use super::super::{default_message, messages};
+    use super::*;
+
+    /// A value `field_schema(field_type)` must reject: one past the range for
+    /// integers, one item or byte too many for bounded lengths, otherwise the
+    /// wrong JSON type or a malformed item.
+    fn out_of_range(field_type: FieldType) -> Value {
+        match field_type {
+            FieldType::U8 => json!(0x100),
+            FieldType::U16 => json!(0x1_0000),
+            FieldType::U24 => json!(0x100_0000),
+            FieldType::U32 => json!(0x1_0000_0000u64),
+            FieldType::U64 => json!(format!("0x1{}", "0".repeat(16))),
+            FieldType::F32 => json!("1.5"),
+            FieldType::Bool => json!(1),
+            FieldType::U256 => json!("00".repeat(33)),
+            FieldType::Str0255 => json!("a".repeat(256)),
+            FieldType::B032 => json!("00".repeat(33)),
+            FieldType::B0255 => json!("00".repeat(256)),
+            FieldType::B064K | FieldType::B016M => json!("0"),
+            FieldType::Seq0255(item) => json!(vec![default_value(*item); 256]),
+            FieldType::Seq064K(item) => json!([out_of_range(*item)]),
+            FieldType::Sv2Option(item) => out_of_range(*item),
+        }
+    }
+
+    fn is_valid(schema: &Value, instance: &Value) -> bool {
+        jsonschema::validator_for(schema).unwrap().is_valid(instance)
+    }
+
+    #[test]
+    fn field_schemas_accept_defaults_and_reject_out_of_range_values() {
+        let families = [
+            FieldType::U8,
+            FieldType::U16,
+            FieldType::U24,
+            FieldType::U32,
+            FieldType::U64,
+            FieldType::F32,
+            FieldType::Bool,
+            FieldType::U256,
+            FieldType::Str0255,
+            FieldType::B032,
+            FieldType::B0255,
+            FieldType::B064K,
+            FieldType::B016M,
+            FieldType::Seq0255(&FieldType::U256),
+            FieldType::Seq064K(&FieldType::U16),
+            FieldType::Sv2Option(&FieldType::U32),
+        ];
+        for field_type in families {
+            let schema = field_schema(field_type);
+            assert!(is_valid(&schema, &default_value(field_type)), "{}", field_type.spec_name());
+            assert!(!is_valid(&schema, &out_of_range(field_type)), "{}", field_type.spec_name());
+        }
+    }
+
+    #[test]
+    fn message_schemas_accept_samples_and_reject_out_of_range_fields() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for descriptor in messages() {
+            let name = descriptor.lookup_name();
+            let schema = json_schema(descriptor);
+            let sample = default_message(descriptor);
+            assert!(is_valid(&schema, &sample), "{}", name);
+            assert!(is_valid(&schema, &random_message(descriptor, &mut rng)), "{}", name);
+
+            for (field, field_type) in descriptor.fields {
+                let mut message = sample.clone();
+                message[*field] = match integer_field_max(descriptor, field) {
+                    Some(maximum) => json!(maximum + 1),
+                    None => out_of_range(*field_type),
+                };
+                assert!(!is_valid(&schema, &message), "{}.{}", name, field);
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Template Distribution protocol messages (`template_distribution_sv2`).
+
+use serde_json::{json, Value};
+use template_distribution_sv2::*;
+
+use super::fields::*;
+use super::Direction::*;
+use super::FieldType::*;
+use super::{from_bytes, to_bytes, CodecError, MessageDescriptor, Subprotocol};
+
+pub(super) const MESSAGES: &[MessageDescriptor] = &[
+    MessageDescriptor::new(Subprotocol::TemplateDistribution, "CoinbaseOutputConstraints", MESSAGE_TYPE_COINBASE_OUTPUT_CONSTRAINTS, CHANNEL_BIT_COINBASE_OUTPUT_CONSTRAINTS, ClientToServer, &[("coinbase_output_max_additional_size", U32), ("coinbase_output_max_additional_sigops", U16)]),
+    MessageDescriptor::new(Subprotocol::TemplateDistribution, "NewTemplate", MESSAGE_TYPE_NEW_TEMPLATE, CHANNEL_BIT_NEW_TEMPLATE, ServerToClient, &[("template_id", U64), ("future_template", Bool), ("version", U32), ("coinbase_tx_version", U32), ("coinbase_prefix", B0255), ("coinbase_tx_input_sequence", U32), ("coinbase_tx_value_remaining", U64), ("coinbase_tx_outputs_count", U32), ("coinbase_tx_outputs", B064K), ("coinbase_tx_locktime", U32), ("merkle_path", Seq0255(&U256))]),
+    MessageDescriptor::new(Subprotocol::TemplateDistribution, "SetNewPrevHash", MESSAGE_TYPE_SET_NEW_PREV_HASH, CHANNEL_BIT_SET_NEW_PREV_HASH, ServerToClient, &[("template_id", U64), ("prev_hash", U256), ("header_timestamp", U32), ("n_bits", U32), ("target", U256)]),
+    MessageDescriptor::new(Subprotocol::TemplateDistribution, "RequestTransactionData", MESSAGE_TYPE_REQUEST_TRANSACTION_DATA, CHANNEL_BIT_REQUEST_TRANSACTION_DATA, ClientToServer, &[("template_id", U64)]),
+    MessageDescriptor::new(Subprotocol::TemplateDistribution, "RequestTransactionDataSuccess", MESSAGE_TYPE_REQUEST_TRANSACTION_DATA_SUCCESS, CHANNEL_BIT_REQUEST_TRANSACTION_DATA_SUCCESS, ServerToClient, &[("template_id", U64), ("excess_data", B064K), ("transaction_list", Seq064K(&B016M))]),
+    MessageDescriptor::new(Subprotocol::TemplateDistribution, "RequestTransactionDataError", MESSAGE_TYPE_REQUEST_TRANSACTION_DATA_ERROR, CHANNEL_BIT_REQUEST_TRANSACTION_DATA_ERROR, ServerToClient, &[("template_id", U64), ("error_code", Str0255)]),
+    MessageDescriptor::new(Subprotocol::TemplateDistribution, "SubmitSolution", MESSAGE_TYPE_SUBMIT_SOLUTION, CHANNEL_BIT_SUBMIT_SOLUTION, ClientToServer, &[("template_id", U64), ("version", U32), ("header_timestamp", U32), ("header_nonce", U32), ("coinbase_tx", B064K)]),
+];
+
+pub(super) fn encode(name: &str, message: &Value) -> Result<Vec<u8>, CodecError> {
+    match name {
+        "CoinbaseOutputConstraints" => to_bytes(CoinbaseOutputConstraints {
+            coinbase_output_max_additional_size: u32_field(message, "coinbase_output_max_additional_size")?,
+            coinbase_output_max_additional_sigops: u16_field(message, "coinbase_output_max_additional_sigops")?,
+        }),
+        "NewTemplate" => to_bytes(NewTemplate {
+            template_id: u64_field(message, "template_id")?,
+            future_template: bool_field(message, "future_template")?,
+            version: u32_field(message, "version")?,
+            coinbase_tx_version: u32_field(message, "coinbase_tx_version")?,
+            coinbase_prefix: bytes_field(message, "coinbase_prefix", "B0_255")?,
+            coinbase_tx_input_sequence: u32_field(message, "coinbase_tx_input_sequence")?,
+            coinbase_tx_value_remaining: u64_field(message, "coinbase_tx_value_remaining")?,
+            coinbase_tx_outputs_count: u32_field(message, "coinbase_tx_outputs_count")?,
+            coinbase_tx_outputs: bytes_field(message, "coinbase_tx_outputs", "B0_64K")?,
+            coinbase_tx_locktime: u32_field(message, "coinbase_tx_locktime")?,
+            merkle_path: empty_seq0255(),
+        }),
+        "SetNewPrevHash" => to_bytes(SetNewPrevHash {
+            template_id: u64_field(message, "template_id")?,
+            prev_hash: bytes_field(message, "prev_hash", "U256")?,
+            header_timestamp: u32_field(message, "header_timestamp")?,
+            n_bits: u32_field(message, "n_bits")?,
+            target: bytes_field(message, "target", "U256")?,
+        }),
+        "RequestTransactionData" => to_bytes(RequestTransactionData {
+            template_id: u64_field(message, "template_id")?,
+        }),
+        "RequestTransactionDataSuccess" => to_bytes(RequestTransactionDataSuccess {
+            template_id: u64_field(message, "template_id")?,
+            excess_data: bytes_field(message, "excess_data", "B0_64K")?,
+            transaction_list: empty_seq064k(),
+        }),
+        "RequestTransactionDataError" => to_bytes(RequestTransactionDataError {
+            template_id: u64_field(message, "template_id")?,
+            error_code: str0255_field(message, "error_code")?,
+        }),
+        "SubmitSolution" => to_bytes(SubmitSolution {
+            template_id: u64_field(message, "template_id")?,
+            version: u32_field(message, "version")?,
+            header_timestamp: u32_field(message, "header_timestamp")?,
+            header_nonce: u32_field(message, "header_nonce")?,
+            coinbase_tx: bytes_field(message, "coinbase_tx", "B0_64K")?,
+        }),
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
+
+pub(super) fn decode(name: &str, payload: &mut [u8]) -> Result<Value, CodecError> {
+    match name {
+        "CoinbaseOutputConstraints" => {
+            let m: CoinbaseOutputConstraints = from_bytes(payload)?;
+            Ok(json!({
+                "coinbase_output_max_additional_size": m.coinbase_output_max_additional_size,
+                "coinbase_output_max_additional_sigops": m.coinbase_output_max_additional_sigops,
+            }))
+        }
+        "NewTemplate" => {
+            let m: NewTemplate = from_bytes(payload)?;
+            Ok(json!({
+                "template_id": m.template_id,
+                "future_template": m.future_template,
+                "version": m.version,
+                "coinbase_tx_version": m.coinbase_tx_version,
+                "coinbase_prefix": hex_json(m.coinbase_prefix.inner_as_ref()),
+                "coinbase_tx_input_sequence": m.coinbase_tx_input_sequence,
+                "coinbase_tx_value_remaining": m.coinbase_tx_value_remaining,
+                "coinbase_tx_outputs_count": m.coinbase_tx_outputs_count,
+                "coinbase_tx_outputs": hex_json(m.coinbase_tx_outputs.inner_as_ref()),
+                "coinbase_tx_locktime": m.coinbase_tx_locktime,
+                "merkle_path": hex_list_json(m.merkle_path.to_vec()),
+            }))
+        }
+        "SetNewPrevHash" => {
+            let m: SetNewPrevHash = from_bytes(payload)?;
+            Ok(json!({
+                "template_id": m.template_id,
+                "prev_hash": hex_json(m.prev_hash.inner_as_ref()),
+                "header_timestamp": m.header_timestamp,
+                "n_bits": m.n_bits,
+                "target": hex_json(m.target.inner_as_ref()),
+            }))
+        }
+        "RequestTransactionData" => {
+            let m: RequestTransactionData = from_bytes(payload)?;
+            Ok(json!({
+                "template_id": m.template_id,
+            }))
+        }
+        "RequestTransactionDataSuccess" => {
+            let m: RequestTransactionDataSuccess = from_bytes(payload)?;
+            Ok(json!({
+                "template_id": m.template_id,
+                "excess_data": hex_json(m.excess_data.inner_as_ref()),
+                "transaction_list": hex_list_json(m.transaction_list.to_vec()),
+            }))
+        }
+        "RequestTransactionDataError" => {
+            let m: RequestTransactionDataError = from_bytes(payload)?;
+            Ok(json!({
+                "template_id": m.template_id,
+                "error_code": str_json("error_code", &m.error_code)?,
+            }))
+        }
+        "SubmitSolution" => {
+            let m: SubmitSolution = from_bytes(payload)?;
+            Ok(json!({
+                "template_id": m.template_id,
+                "version": m.version,
+                "header_timestamp": m.header_timestamp,
+                "header_nonce": m.header_nonce,
+                "coinbase_tx": hex_json(m.coinbase_tx.inner_as_ref()),
+            }))
+        }
+        _ => Err(CodecError::UnknownMessageType(name.to_string())),
+    }
+}
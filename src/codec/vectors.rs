@@ -0,0 +1,172 @@
+//! Test vectors: labeled encodings of a message at the limits of its field
+//! types, and intentionally malformed ones, each with the outcome a conforming
+//! decoder should produce.
+
+use serde_json::{json, Value};
+
+use super::common::integer_field_max;
+use super::fields::f32_json;
+use super::sample::{default_message, default_value};
+use super::{
+    annotate, decode, decode_frame_message, encode, encode_frame, fields_length, CodecError, FieldType,
+    MessageDescriptor, FRAME_HEADER_SIZE,
+};
+use crate::tlv;
+use crate::types::TestVector;
+
+/// Boundary and malformed vectors for `descriptor`. B0_16M fields are only
+/// covered empty, as their maximum encodes to 16 MiB.
+pub fn test_vectors(descriptor: &MessageDescriptor) -> Result<Vec<TestVector>, CodecError> {
+    let mut vectors = Vectors {
+        descriptor,
+        name: descriptor.lookup_name(),
+        vectors: Vec::new(),
+    };
+    let minimal = default_message(descriptor);
+    let payload = vectors.boundary("every field at its minimum", &minimal)?;
+
+    for (name, field_type) in descriptor.fields {
+        let value = match field_type {
+            FieldType::U8 | FieldType::U16 | FieldType::U24 | FieldType::U32 | FieldType::U64 => {
+                let max = integer_field_max(descriptor, name).unwrap_or(match field_type {
+                    FieldType::U8 => u8::MAX as u64,
+                    FieldType::U16 => u16::MAX as u64,
+                    FieldType::U24 => 0xff_ffff,
+                    FieldType::U32 => u32::MAX as u64,
+                    _ => u64::MAX,
+                });
+                Some((format!("{} = {}", name, max), json!(max)))
+            }
+            FieldType::F32 => Some((format!("{} = F32 max", name), f32_json(f32::MAX))),
+            FieldType::Bool => Some((format!("{} = true", name), json!(true))),
+            FieldType::U256 => Some((format!("{} with every bit set", name), json!(hex::encode([0xff; 32])))),
+            FieldType::Str0255 => Some((format!("{} at 255 bytes", name), json!("a".repeat(255)))),
+            FieldType::B032 | FieldType::B0255 | FieldType::B064K => {
+                let max = field_type.size_range().1 - field_type.size_range().0;
+                Some((format!("{} at {} bytes", name, max), json!(hex::encode(vec![0xaa; max]))))
+            }
+            FieldType::B016M => None,
+            FieldType::Seq0255(item) => {
+                Some((format!("{} with 255 items", name), Value::Array(vec![default_value(**item); 255])))
+            }
+            // 256 is the first count that needs both bytes of the prefix
+            FieldType::Seq064K(item) => {
+                Some((format!("{} with 256 items", name), Value::Array(vec![default_value(**item); 256])))
+            }
+            FieldType::Sv2Option(item) => Some((format!("{} present", name), default_value(**item))),
+        };
+        if let Some((label, value)) = value {
+            let mut message = minimal.clone();
+            message[*name] = value;
+            vectors.boundary(&label, &message)?;
+        }
+    }
+
+    // Bytes after the last field are the TLV region: whole TLV fields are
+    // valid, but fewer bytes than a TLV header are not
+    let mut empty_tlv = payload.clone();
+    empty_tlv.extend_from_slice(&[0x00; 5]);
+    vectors.push("one empty TLV field after the last field", "boundary", false, empty_tlv, true);
+    let mut trailing = payload.clone();
+    trailing.extend_from_slice(&[0x00, 0x00, 0x00]);
+    vectors.push("3 bytes after the last field", "malformed", false, trailing, false);
+
+    let mut truncated = payload.clone();
+    truncated.pop();
+    vectors.push("payload missing its last byte", "malformed", false, truncated, false);
+
+    for annotation in annotate(descriptor, &payload, false)? {
+        if !(annotation.field.ends_with(".length") || annotation.field.ends_with(".count")) {
+            continue;
+        }
+        let declared = (payload.len() - annotation.offset - annotation.length + 1) as u64;
+        if annotation.length < 8 && declared >= 1 << (8 * annotation.length) {
+            continue;
+        }
+        let mut bytes = payload.clone();
+        bytes[annotation.offset..annotation.offset + annotation.length]
+            .copy_from_slice(&declared.to_le_bytes()[..annotation.length]);
+        let label = format!("{} = {}, past the end of the payload", annotation.field, declared);
+        vectors.push(&label, "malformed", false, bytes, false);
+    }
+
+    for (name, _) in descriptor.fields.iter().filter(|(_, field_type)| *field_type == FieldType::Str0255) {
+        let mut message = minimal.clone();
+        message[*name] = json!("a");
+        let mut bytes = encode(&vectors.name, &message)?;
+        let offset = annotate(descriptor, &bytes, false)?
+            .into_iter()
+            .find(|annotation| annotation.field == *name)
+            .map(|annotation| annotation.offset)
+            .expect("every field is annotated");
+        bytes[offset] = 0xff;
+        vectors.push(&format!("{} is not valid UTF-8", name), "malformed", false, bytes, false);
+    }
+
+    let (_, frame) = encode_frame(0, descriptor.msg_type, descriptor.channel_msg, &payload)?;
+    vectors.push("complete frame", "boundary", true, frame.clone(), true);
+    vectors.push("frame header only, missing one byte", "malformed", true, frame[..5].to_vec(), false);
+    vectors.push("frame missing the last payload byte", "malformed", true, frame[..frame.len() - 1].to_vec(), false);
+    let mut longer = frame.clone();
+    longer.push(0x00);
+    vectors.push("frame with a byte beyond msg_length", "malformed", true, longer, false);
+
+    Ok(vectors.vectors)
+}
+
+/// Vectors larger than this are listed without their decoded message, which
+/// would only repeat the hex.
+const MAX_MESSAGE_BYTES: usize = 1024;
+
+struct Vectors<'a> {
+    descriptor: &'a MessageDescriptor,
+    name: String,
+    vectors: Vec<TestVector>,
+}
+
+impl Vectors<'_> {
+    /// Add the encoding of a valid message and return its payload.
+    fn boundary(&mut self, label: &str, message: &Value) -> Result<Vec<u8>, CodecError> {
+        let payload = encode(&self.name, message)?;
+        self.push(label, "boundary", false, payload.clone(), true);
+        Ok(payload)
+    }
+
+    /// Add a vector together with what this server makes of it: the message
+    /// fields, then every byte after them as complete TLV fields, as
+    /// `decode_message` reads it.
+    fn push(&mut self, label: &str, category: &str, framed: bool, bytes: Vec<u8>, decodes: bool) {
+        let header_size = if framed { FRAME_HEADER_SIZE } else { 0 };
+        let decoded = match framed {
+            true => decode_frame_message(&bytes).map(|(_, message)| message),
+            false => decode(&self.name, &bytes),
+        }
+        .and_then(|message| Ok((message, fields_length(self.descriptor, &bytes[header_size..])?)))
+        .map_err(|e| e.to_string())
+        .and_then(|(message, fields_length)| {
+            let offset = header_size + fields_length;
+            match tlv::split_fields(&bytes[offset..], offset).1 {
+                Some((_, error)) => Err(error),
+                None => Ok(message),
+            }
+        });
+        let (message, decoder_error) = match decoded {
+            Ok(message) => (Some(message), None),
+            Err(e) => (None, Some(e)),
+        };
+        self.vectors.push(TestVector {
+            label: label.to_string(),
+            category: category.to_string(),
+            framed,
+            expected: outcome(decodes),
+            outcome: outcome(message.is_some()),
+            message: message.filter(|_| bytes.len() <= MAX_MESSAGE_BYTES),
+            hex: hex::encode(bytes),
+            decoder_error,
+        });
+    }
+}
+
+fn outcome(decodes: bool) -> String {
+    if decodes { "decodes" } else { "rejected" }.to_string()
+}
@@ -0,0 +1,95 @@
+//! Compare a message as given to the encoder with the same message decoded
+//! from the bytes the encoder produced.
+
+use serde_json::{Map, Value};
+
+use super::fields::parse_integer;
+use super::{FieldType, MessageDescriptor};
+use crate::types::FieldMismatch;
+
+/// List every input field that did not survive the round trip unchanged.
+/// Values are compared after normalisation, so `"0x10"` and `16` or `"AB"`
+/// and `"ab"` are treated as equal.
+pub fn round_trip_mismatches(descriptor: &MessageDescriptor, input: &Value, decoded: &Value) -> Vec<FieldMismatch> {
+    let empty = Map::new();
+    let input = input.as_object().unwrap_or(&empty);
+    let mut mismatches = Vec::new();
+
+    for (name, value) in input {
+        if !descriptor.fields.iter().any(|(field, _)| field == name) {
+            mismatches.push(FieldMismatch {
+                field: name.clone(),
+                kind: "dropped".to_string(),
+                input: value.clone(),
+                decoded: None,
+            });
+        }
+    }
+    for (name, field_type) in descriptor.fields {
+        let value = input.get(*name).unwrap_or(&Value::Null);
+        compare(name.to_string(), *field_type, value, &decoded[*name], &mut mismatches);
+    }
+    mismatches
+}
+
+fn compare(field: String, field_type: FieldType, input: &Value, decoded: &Value, mismatches: &mut Vec<FieldMismatch>) {
+    let kind = match field_type {
+        FieldType::Seq0255(item) | FieldType::Seq064K(item) => {
+            match (input.as_array(), decoded.as_array()) {
+                (Some(inputs), Some(outputs)) if inputs.len() == outputs.len() => {
+                    for (index, (input, decoded)) in inputs.iter().zip(outputs).enumerate() {
+                        compare(format!("{}[{}]", field, index), *item, input, decoded, mismatches);
+                    }
+                    None
+                }
+                (Some(inputs), Some(outputs)) if outputs.len() < inputs.len() => Some("truncated"),
+                _ => Some("coerced"),
+            }
+        }
+        FieldType::Sv2Option(item) => match (input, decoded) {
+            (Value::Null, Value::Null) => None,
+            (Value::Null, _) | (_, Value::Null) => Some("coerced"),
+            _ => {
+                compare(field.clone(), *item, input, decoded, mismatches);
+                None
+            }
+        },
+        FieldType::U8 | FieldType::U16 | FieldType::U24 | FieldType::U32 | FieldType::U64 => {
+            // Non-numeric strings are named values (e.g. `protocol: "mining"`)
+            // that the encoder has already resolved.
+            match (parse_integer(&field, input), decoded.as_u64()) {
+                (Ok(input), Some(decoded)) if input != decoded => Some("coerced"),
+                _ => None,
+            }
+        }
+        FieldType::F32 => match (input.as_f64(), decoded.as_f64()) {
+            (Some(input), Some(decoded)) if input == decoded => None,
+            _ => Some("coerced"),
+        },
+        FieldType::Bool => (input != decoded).then_some("coerced"),
+        FieldType::Str0255 => text_mismatch(input.as_str().unwrap_or_default(), decoded.as_str().unwrap_or_default()),
+        FieldType::U256 | FieldType::B032 | FieldType::B0255 | FieldType::B064K | FieldType::B016M => {
+            let input = input.as_str().unwrap_or_default().trim_start_matches("0x").to_lowercase();
+            text_mismatch(&input, decoded.as_str().unwrap_or_default())
+        }
+    };
+
+    if let Some(kind) = kind {
+        mismatches.push(FieldMismatch {
+            field,
+            kind: kind.to_string(),
+            input: input.clone(),
+            decoded: Some(decoded.clone()),
+        });
+    }
+}
+
+fn text_mismatch(input: &str, decoded: &str) -> Option<&'static str> {
+    if input == decoded {
+        None
+    } else if input.starts_with(decoded) {
+        Some("truncated")
+    } else {
+        Some("coerced")
+    }
+}
@@ -1,56 +1,154 @@
+use rand::{rngs::StdRng, SeedableRng};
+use rmcp::{transport::stdio, ServiceExt};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::Arc;
 
-mod types;
+pub mod capture;
+pub mod codec;
+mod mcp;
+pub mod registry;
+pub mod tlv;
+pub mod types;
+use registry::ProtocolRegistry;
 use types::*;
 
 #[derive(Clone)]
-pub struct StratumV2MCPServer;
+pub struct StratumV2MCPServer {
+    registry: Arc<ProtocolRegistry>,
+}
+
+impl Default for StratumV2MCPServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl StratumV2MCPServer {
     pub fn new() -> Self {
-        StratumV2MCPServer
+        Self::with_registry(ProtocolRegistry::builtin())
+    }
+
+    pub fn with_registry(registry: ProtocolRegistry) -> Self {
+        StratumV2MCPServer {
+            registry: Arc::new(registry),
+        }
     }
 
     // Public async methods for CLI compatibility  
     pub async fn analyze_protocol_spec(&self) -> String {
-        self.analyze_protocol_spec_blocking()
+        serde_json::to_string_pretty(&self.analyze_protocol_spec_blocking()).unwrap()
+    }
+
+    pub async fn list_message_types(&self, subprotocol: Option<String>, direction: Option<String>) -> String {
+        serde_json::to_string_pretty(&self.list_message_types_blocking(subprotocol, direction)).unwrap()
+    }
+
+    pub async fn get_message_info(&self, message_type: String) -> String {
+        serde_json::to_string_pretty(&self.get_message_info_blocking(message_type)).unwrap()
     }
 
-    pub async fn list_message_types(&self) -> String {
-        self.list_message_types_blocking()
+    pub async fn get_message_schema(&self, message_type: String) -> String {
+        serde_json::to_string_pretty(&self.get_message_schema_blocking(message_type)).unwrap()
     }
 
     pub async fn list_extensions(&self) -> String {
-        self.list_extensions_blocking()
+        serde_json::to_string_pretty(&self.list_extensions_blocking()).unwrap()
     }
 
     pub async fn get_extension_info(&self, extension_type: u16) -> String {
-        self.get_extension_info_blocking(extension_type)
+        serde_json::to_string_pretty(&self.get_extension_info_blocking(extension_type)).unwrap()
+    }
+
+    pub async fn create_tlv_field(
+        &self,
+        extension_type: u16,
+        field_type: u8,
+        value: String,
+        value_type: Option<String>,
+    ) -> String {
+        serde_json::to_string_pretty(&self.create_tlv_field_blocking(extension_type, field_type, value, value_type))
+            .unwrap()
+    }
+
+    pub async fn build_tlv_list(
+        &self,
+        fields: Vec<CreateTLVFieldRequest>,
+        negotiated_extensions: Option<Vec<u16>>,
+        message_type: Option<String>,
+    ) -> String {
+        serde_json::to_string_pretty(&self.build_tlv_list_blocking(fields, negotiated_extensions, message_type)).unwrap()
+    }
+
+    pub async fn parse_tlv_fields(&self, bytes: Vec<u8>, lenient: bool) -> String {
+        serde_json::to_string_pretty(&self.parse_tlv_fields_blocking(bytes, lenient, 0)).unwrap()
+    }
+
+    pub async fn validate_tlv_field(
+        &self,
+        extension_type: u16,
+        field_type: u8,
+        value: String,
+        value_type: Option<String>,
+        negotiated_extensions: Option<Vec<u16>>,
+        message_type: Option<String>,
+    ) -> String {
+        serde_json::to_string_pretty(&self.validate_tlv_field_blocking(
+            extension_type,
+            field_type,
+            value,
+            value_type,
+            negotiated_extensions,
+            message_type,
+        ))
+        .unwrap()
+    }
+
+    pub async fn generate_test_message(
+        &self,
+        message_type: String,
+        overrides: Option<Value>,
+        random: bool,
+        seed: Option<u64>,
+        encode: bool,
+    ) -> String {
+        serde_json::to_string_pretty(&self.generate_test_message_blocking(message_type, overrides, random, seed, encode))
+            .unwrap()
+    }
+
+    pub async fn generate_test_vectors(&self, message_type: String) -> String {
+        serde_json::to_string_pretty(&self.generate_test_vectors_blocking(message_type)).unwrap()
     }
 
-    pub async fn create_tlv_field(&self, extension_type: u16, field_type: u8, value: String) -> String {
-        self.create_tlv_field_blocking(extension_type, field_type, value)
+    pub async fn encode_message(
+        &self,
+        message: Value,
+        message_type: String,
+        verify: bool,
+        tlvs: Vec<CreateTLVFieldRequest>,
+        frame: bool,
+    ) -> String {
+        serde_json::to_string_pretty(&self.encode_message_blocking(message, message_type, verify, tlvs, frame)).unwrap()
     }
 
-    pub async fn parse_tlv_fields(&self, bytes: Vec<u8>) -> String {
-        self.parse_tlv_fields_blocking(bytes)
+    pub async fn decode_message(&self, bytes: Vec<u8>, message_type: Option<String>, annotate: bool) -> String {
+        serde_json::to_string_pretty(&self.decode_message_blocking(bytes, message_type, annotate)).unwrap()
     }
 
-    pub async fn validate_tlv_field(&self, extension_type: u16, field_type: u8, value: String) -> String {
-        self.validate_tlv_field_blocking(extension_type, field_type, value)
+    pub async fn encode_frame(&self, message_type: String, payload: Vec<u8>, extension_type: u16, channel_msg: Option<bool>) -> String {
+        serde_json::to_string_pretty(&self.encode_frame_blocking(message_type, payload, extension_type, channel_msg)).unwrap()
     }
 
-    pub async fn generate_test_message(&self, message_type: String) -> String {
-        self.generate_test_message_blocking(message_type)
+    pub async fn decode_frame(&self, bytes: Vec<u8>) -> String {
+        serde_json::to_string_pretty(&self.decode_frame_blocking(bytes)).unwrap()
     }
 
-    pub async fn encode_message(&self, message: Value, message_type: String) -> String {
-        self.encode_message_blocking(message, message_type)
+    pub async fn decode_stream(&self, bytes: Vec<u8>) -> String {
+        serde_json::to_string_pretty(&self.decode_stream_blocking(bytes)).unwrap()
     }
 
-    pub async fn decode_message(&self, bytes: Vec<u8>, message_type: String) -> String {
-        self.decode_message_blocking(bytes, message_type)
+    pub async fn diff_messages(&self, left: Vec<u8>, right: Vec<u8>, message_type: Option<String>) -> String {
+        serde_json::to_string_pretty(&self.diff_messages_blocking(left, right, message_type)).unwrap()
     }
 
     pub async fn demonstrate_advanced_features(&self) -> String {
@@ -72,438 +170,801 @@ impl StratumV2MCPServer {
     // MCP Server Implementation
     pub async fn run_mcp_server(&self) -> anyhow::Result<()> {
         tracing::info!("Starting Stratum V2 MCP Server");
-        
-        // Simple JSON-RPC over stdin/stdout
-        let stdin = tokio::io::stdin();
-        let stdout = tokio::io::stdout();
-        
-        // For now, just keep the server running
-        // A proper MCP implementation would handle JSON-RPC messages
-        // This is a placeholder until we can properly integrate with the rmcp crate
-        
+
+        // JSON-RPC over stdin/stdout; stdout is reserved for protocol messages
         eprintln!("MCP Server started - listening on stdin/stdout");
-        
-        // Keep the server running indefinitely
-        loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-        }
+
+        let service = self.clone().serve(stdio()).await?;
+        service.waiting().await?;
+
+        Ok(())
     }
 
     // Blocking implementations
-    fn analyze_protocol_spec_blocking(&self) -> String {
-        let spec = ProtocolSpec {
-            version: "2.0.0".to_string(),
-            description: "Stratum V2 is a comprehensive mining protocol suite with multiple subprotocols, binary message format, noise encryption, and role-based architecture.".to_string(),
-            message_types: vec![
-                MessageType {
-                    name: "SetupConnection".to_string(),
-                    direction: "Client -> Server".to_string(),
-                    fields: vec![
-                        MessageField { name: "protocol".to_string(), field_type: "STR0_255".to_string(), description: "Protocol identifier".to_string(), required: true },
-                        MessageField { name: "min_version".to_string(), field_type: "U16".to_string(), description: "Minimum supported protocol version".to_string(), required: true },
-                        MessageField { name: "max_version".to_string(), field_type: "U16".to_string(), description: "Maximum supported protocol version".to_string(), required: true },
-                        MessageField { name: "flags".to_string(), field_type: "U32".to_string(), description: "Connection flags".to_string(), required: true },
-                        MessageField { name: "endpoint_host".to_string(), field_type: "STR0_255".to_string(), description: "Host endpoint".to_string(), required: true },
-                        MessageField { name: "endpoint_port".to_string(), field_type: "U16".to_string(), description: "Port endpoint".to_string(), required: true },
-                        MessageField { name: "vendor".to_string(), field_type: "STR0_255".to_string(), description: "Vendor identifier".to_string(), required: true },
-                        MessageField { name: "hardware_version".to_string(), field_type: "STR0_255".to_string(), description: "Hardware version".to_string(), required: true },
-                        MessageField { name: "firmware".to_string(), field_type: "STR0_255".to_string(), description: "Firmware version".to_string(), required: true },
-                        MessageField { name: "device_id".to_string(), field_type: "STR0_255".to_string(), description: "Device identifier".to_string(), required: true },
-                    ],
-                    description: "Initial connection setup message with noise handshake support".to_string(),
-                },
-                MessageType {
-                    name: "SubmitSharesStandard".to_string(),
-                    direction: "Client -> Server".to_string(),
-                    fields: vec![
-                        MessageField { name: "channel_id".to_string(), field_type: "U32".to_string(), description: "Mining channel identifier".to_string(), required: true },
-                        MessageField { name: "sequence_number".to_string(), field_type: "U32".to_string(), description: "Sequence number for ordering".to_string(), required: true },
-                        MessageField { name: "job_id".to_string(), field_type: "U32".to_string(), description: "Job identifier".to_string(), required: true },
-                        MessageField { name: "nonce".to_string(), field_type: "U32".to_string(), description: "Nonce value".to_string(), required: true },
-                        MessageField { name: "ntime".to_string(), field_type: "U32".to_string(), description: "Block time".to_string(), required: true },
-                        MessageField { name: "version".to_string(), field_type: "U32".to_string(), description: "Block version".to_string(), required: true },
-                    ],
-                    description: "Standard share submission with binary encoding".to_string(),
-                },
-                MessageType {
-                    name: "NewTemplate".to_string(),
-                    direction: "Server -> Client".to_string(),
-                    fields: vec![
-                        MessageField { name: "template_id".to_string(), field_type: "U64".to_string(), description: "Template identifier".to_string(), required: true },
-                        MessageField { name: "future_template".to_string(), field_type: "BOOL".to_string(), description: "Whether this is a future template".to_string(), required: true },
-                        MessageField { name: "version".to_string(), field_type: "U32".to_string(), description: "Template version".to_string(), required: true },
-                        MessageField { name: "coinbase_tx_version".to_string(), field_type: "U32".to_string(), description: "Coinbase transaction version".to_string(), required: true },
-                        MessageField { name: "coinbase_prefix".to_string(), field_type: "B0_64K".to_string(), description: "Coinbase prefix".to_string(), required: true },
-                        MessageField { name: "coinbase_tx_suffix".to_string(), field_type: "B0_64K".to_string(), description: "Coinbase suffix".to_string(), required: true },
-                    ],
-                    description: "Template distribution protocol message".to_string(),
-                },
-            ],
-            extensions: vec![
-                ExtensionInfo {
-                    extension_type: 0x0001,
-                    name: "Extensions Negotiation".to_string(),
-                    description: "Negotiates support for other protocol extensions between clients and servers".to_string(),
-                    negotiation_required: true,
-                    messages: vec!["RequestExtensions".to_string(), "RequestExtensions.Success".to_string(), "RequestExtensions.Error".to_string()],
-                    tlv_fields: vec![],
-                },
-                ExtensionInfo {
-                    extension_type: 0x0002,
-                    name: "Worker-Specific Hashrate Tracking".to_string(),
-                    description: "Enables mining pools to track individual workers within extended channels".to_string(),
-                    negotiation_required: true,
-                    messages: vec![],
-                    tlv_fields: vec![
-                        TLVFieldInfo {
-                            field_type: 0x01,
-                            name: "user_identity".to_string(),
-                            data_type: "UTF-8 string".to_string(),
-                            max_length: Some(32),
-                            description: "Worker name/identifier".to_string(),
-                        }
-                    ],
-                }
-            ],
-            security_features: vec![
-                "Noise protocol encryption for secure communication".to_string(),
-                "Binary protocol with efficient message encoding".to_string(),
-                "TLV (Type-Length-Value) extension support".to_string(),
-                "Role-based architecture (Pool, Proxy, Miner, Job Declarator)".to_string(),
-                "Multiple subprotocols (Mining, Job Declaration, Template Distribution)".to_string(),
-                "Channel-based communication with sequence numbers".to_string(),
-                "Backward compatibility with Stratum V1 via translator".to_string(),
-            ],
+    fn analyze_protocol_spec_blocking(&self) -> ProtocolSpec {
+        self.registry.spec().clone()
+    }
+
+    fn list_message_types_blocking(&self, subprotocol: Option<String>, direction: Option<String>) -> ListMessageTypesResponse {
+        // Filters ignore case and punctuation, so "JobDeclaration" matches "job_declaration"
+        let normalize = |text: &str| -> String {
+            text.chars().filter(char::is_ascii_alphanumeric).collect::<String>().to_lowercase()
         };
-        
-        serde_json::to_string_pretty(&spec).unwrap()
-    }
-
-    fn list_message_types_blocking(&self) -> String {
-        let message_types = vec![
-            "SetupConnection",
-            "SetupConnectionSuccess", 
-            "SetupConnectionError",
-            "OpenStandardMiningChannel",
-            "OpenStandardMiningChannelSuccess",
-            "OpenStandardMiningChannelError",
-            "OpenExtendedMiningChannel", 
-            "OpenExtendedMiningChannelSuccess",
-            "OpenExtendedMiningChannelError",
-            "UpdateChannel",
-            "UpdateChannelError",
-            "SubmitSharesStandard",
-            "SubmitSharesSuccess",
-            "SubmitSharesError", 
-            "SubmitSharesExtended",
-            "SetNewPrevHash",
-            "SetTarget",
-            "SetCustomMiningJob",
-            "NewTemplate",
-            "DeclareTransaction",
-            "Reconnect",
-            "CloseChannel"
-        ];
-        
-        serde_json::to_string_pretty(&message_types).unwrap()
-    }
-
-    fn list_extensions_blocking(&self) -> String {
-        let extensions = vec![
-            ExtensionInfo {
-                extension_type: 0x0001,
-                name: "Extensions Negotiation".to_string(),
-                description: "Negotiates support for other protocol extensions between clients and servers".to_string(),
-                negotiation_required: true,
-                messages: vec!["RequestExtensions".to_string(), "RequestExtensions.Success".to_string(), "RequestExtensions.Error".to_string()],
-                tlv_fields: vec![],
-            },
-            ExtensionInfo {
-                extension_type: 0x0002,
-                name: "Worker-Specific Hashrate Tracking".to_string(),
-                description: "Enables mining pools to track individual workers within extended channels".to_string(),
-                negotiation_required: true,
-                messages: vec![],
-                tlv_fields: vec![
-                    TLVFieldInfo {
-                        field_type: 0x01,
-                        name: "user_identity".to_string(),
-                        data_type: "UTF-8 string".to_string(),
-                        max_length: Some(32),
-                        description: "Worker name/identifier".to_string(),
-                    }
-                ],
+        let error = |message: String| ListMessageTypesResponse {
+            message_types: Vec::new(),
+            error: Some(message),
+        };
+
+        let message_types = self.registry.message_types();
+        if let Some(subprotocol) = &subprotocol {
+            if !message_types.iter().any(|message| normalize(&message.subprotocol) == normalize(subprotocol)) {
+                return error(format!(
+                    "Unknown subprotocol {}: use common, mining, job_declaration or template_distribution",
+                    subprotocol
+                ));
             }
-        ];
-        
-        serde_json::to_string_pretty(&extensions).unwrap()
-    }
-
-    fn get_extension_info_blocking(&self, extension_type: u16) -> String {
-        let extension_info = match extension_type {
-            0x0001 => Some(ExtensionInfo {
-                extension_type: 0x0001,
-                name: "Extensions Negotiation".to_string(),
-                description: "This extension defines the basic protocol for requesting and negotiating support for other protocol extensions between clients and servers.".to_string(),
-                negotiation_required: true,
-                messages: vec!["RequestExtensions".to_string(), "RequestExtensions.Success".to_string(), "RequestExtensions.Error".to_string()],
-                tlv_fields: vec![],
-            }),
-            0x0002 => Some(ExtensionInfo {
-                extension_type: 0x0002,
-                name: "Worker-Specific Hashrate Tracking".to_string(),
-                description: "This extension modifies the existing SubmitSharesExtended message by introducing a new TLV field that contains the user_identity (worker name).".to_string(),
-                negotiation_required: true,
-                messages: vec![],
-                tlv_fields: vec![
-                    TLVFieldInfo {
-                        field_type: 0x01,
-                        name: "user_identity".to_string(),
-                        data_type: "UTF-8 string".to_string(),
-                        max_length: Some(32),
-                        description: "Worker name/identifier for hashrate tracking".to_string(),
-                    }
-                ],
-            }),
-            _ => None,
+        }
+        let subprotocol = subprotocol.as_deref().map(normalize);
+        let direction = match &direction {
+            None => None,
+            Some(text) => match normalize(text).as_str() {
+                "clienttoserver" | "upstream" => Some(codec::Direction::ClientToServer),
+                "servertoclient" | "downstream" => Some(codec::Direction::ServerToClient),
+                _ => return error(format!("Unknown direction {}: use client_to_server or server_to_client", text)),
+            },
         };
 
-        let response = GetExtensionInfoResponse {
-            extension_info: extension_info.clone(),
+        let message_types = message_types
+            .iter()
+            .filter(|message| subprotocol.as_ref().is_none_or(|subprotocol| normalize(&message.subprotocol) == *subprotocol))
+            .filter(|message| {
+                direction.is_none_or(|direction| {
+                    message.direction == direction.label() || message.direction == codec::Direction::Both.label()
+                })
+            })
+            .cloned()
+            .collect();
+
+        ListMessageTypesResponse {
+            message_types,
+            error: None,
+        }
+    }
+
+    fn get_message_info_blocking(&self, message_type: String) -> GetMessageInfoResponse {
+        let message_info = resolve_message(&message_type).map(|descriptor| self.registry.message_type(descriptor).cloned());
+
+        match message_info {
+            Ok(Some(message_info)) => GetMessageInfoResponse {
+                message_info: Some(message_info),
+                error: None,
+            },
+            Ok(None) => GetMessageInfoResponse {
+                message_info: None,
+                error: Some(format!("No catalog entry for message type {}", message_type)),
+            },
+            Err(e) => GetMessageInfoResponse {
+                message_info: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+
+    fn get_message_schema_blocking(&self, message_type: String) -> GetMessageSchemaResponse {
+        match resolve_message(&message_type) {
+            Ok(descriptor) => {
+                let mut schema = codec::json_schema(descriptor);
+                if let Some(message) = self.registry.message_type(descriptor) {
+                    schema["description"] = json!(message.description);
+                }
+                GetMessageSchemaResponse {
+                    message_type: Some(descriptor.lookup_name()),
+                    schema: Some(schema),
+                    error: None,
+                }
+            }
+            Err(e) => GetMessageSchemaResponse {
+                message_type: None,
+                schema: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+
+    fn list_extensions_blocking(&self) -> ListExtensionsResponse {
+        ListExtensionsResponse {
+            extensions: self.registry.extensions().to_vec(),
+        }
+    }
+
+    fn get_extension_info_blocking(&self, extension_type: u16) -> GetExtensionInfoResponse {
+        let extension_info = self.registry.extension(extension_type).cloned();
+
+        GetExtensionInfoResponse {
             error: if extension_info.is_none() {
                 Some(format!("Unknown extension type 0x{:04x}", extension_type))
             } else {
                 None
             },
-        };
-        
-        serde_json::to_string_pretty(&response).unwrap()
+            extension_info,
+        }
     }
 
-    fn create_tlv_field_blocking(&self, extension_type: u16, field_type: u8, value: String) -> String {
-        let value_bytes = value.as_bytes().to_vec();
-        
-        if value_bytes.len() > 65535 {
-            let response = CreateTLVFieldResponse {
-                tlv_field: None,
-                encoded_bytes: None,
-                error: Some(format!("Value too long: {} bytes (max 65535)", value_bytes.len())),
-            };
-            return serde_json::to_string_pretty(&response).unwrap();
-        }
+    /// Build a TLV field. Without an explicit `value_type`, the value is read
+    /// according to the field's registered data type, or as UTF-8 text for
+    /// unregistered fields.
+    fn create_tlv_field_blocking(
+        &self,
+        extension_type: u16,
+        field_type: u8,
+        value: String,
+        value_type: Option<String>,
+    ) -> CreateTLVFieldResponse {
+        let error = |value_type: Option<String>, error: String| CreateTLVFieldResponse {
+            tlv_field: None,
+            value_type,
+            encoded_bytes: None,
+            error: Some(error),
+        };
+
+        let format = match self.tlv_value_format(extension_type, field_type, value_type.as_deref()) {
+            Ok(format) => format,
+            Err(e) => return error(None, e),
+        };
+        let value_type = Some(format.name().to_string());
+        // Registered fields are held to their definition, as in validate_tlv_field
+        let value_bytes = tlv_value_bytes(format, &value).and_then(|bytes| {
+            match self.registry.tlv_field(extension_type, field_type) {
+                Some(field) => tlv::decode_field_value(field, &bytes).map(|_| bytes),
+                None => Ok(bytes),
+            }
+        });
+        let value_bytes = match value_bytes {
+            Ok(bytes) => bytes,
+            Err(e) => return error(value_type, e),
+        };
 
         let tlv_field = TLVField {
             extension_type,
             field_type,
             length: value_bytes.len() as u16,
-            value: value_bytes.clone(),
+            value: hex::encode(&value_bytes),
         };
 
-        // Use proper binary encoding with SV2 format
-        let mut encoded_bytes = Vec::new();
-        encoded_bytes.extend_from_slice(&extension_type.to_le_bytes());
-        encoded_bytes.push(field_type);
-        encoded_bytes.extend_from_slice(&tlv_field.length.to_le_bytes());
-        encoded_bytes.extend_from_slice(&value_bytes);
+        let encoded_bytes = tlv::encode_tlv(extension_type, field_type, &value_bytes);
 
-        let response = CreateTLVFieldResponse {
+        CreateTLVFieldResponse {
             tlv_field: Some(tlv_field),
+            value_type,
             encoded_bytes: Some(hex::encode(&encoded_bytes)),
             error: None,
-        };
-        
-        serde_json::to_string_pretty(&response).unwrap()
+        }
+    }
+
+    /// How to read a TLV value: `value_type` if given, otherwise the field's
+    /// registered data type, or UTF-8 text for unregistered fields.
+    fn tlv_value_format(&self, extension_type: u16, field_type: u8, value_type: Option<&str>) -> Result<tlv::TlvValueFormat, String> {
+        match value_type {
+            Some(value_type) => tlv::TlvValueFormat::parse(value_type),
+            None => Ok(match self.registry.tlv_field(extension_type, field_type) {
+                Some(field) => tlv::TlvValueFormat::for_data_type(tlv::TlvDataType::from_data_type(&field.data_type)),
+                None => tlv::TlvValueFormat::Utf8,
+            }),
+        }
     }
 
-    fn parse_tlv_fields_blocking(&self, bytes: Vec<u8>) -> String {
-        let mut tlv_fields = Vec::new();
+    /// Build the TLV block appended to a message. Every field must be
+    /// registered, belong to a negotiated extension, be allowed on
+    /// `message_type` and pass validation, and each extension_type/field_type
+    /// pair may appear once. Fields are emitted in ascending extension_type,
+    /// then field_type order.
+    fn build_tlv_list_blocking(
+        &self,
+        fields: Vec<CreateTLVFieldRequest>,
+        negotiated_extensions: Option<Vec<u16>>,
+        message_type: Option<String>,
+    ) -> BuildTLVListResponse {
         let mut errors = Vec::new();
-        let mut offset = 0;
+        let mut values = Vec::new();
 
-        while offset + 5 <= bytes.len() {
-            if offset + 5 > bytes.len() {
-                errors.push("Insufficient bytes for TLV header".to_string());
-                break;
+        let message = match message_type.as_deref().map(resolve_message).transpose() {
+            Ok(message) => message,
+            Err(e) => {
+                return BuildTLVListResponse {
+                    tlv_fields: Vec::new(),
+                    encoded_bytes: None,
+                    total_length: None,
+                    errors: vec![e.to_string()],
+                }
             }
+        };
 
-            let extension_type = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
-            let field_type = bytes[offset + 2];
-            let length = u16::from_le_bytes([bytes[offset + 3], bytes[offset + 4]]);
-
-            if offset + 5 + length as usize > bytes.len() {
-                errors.push(format!("Insufficient bytes for TLV value (need {}, have {})", 
-                    length, bytes.len() - offset - 5));
-                break;
+        for (index, field) in fields.iter().enumerate() {
+            let label = format!("Field {} (0x{:04x}/{})", index, field.extension_type, field.field_type);
+            let Some(extension) = self.registry.extension(field.extension_type) else {
+                errors.push(format!("{}: Unknown extension type 0x{:04x}", label, field.extension_type));
+                continue;
+            };
+            let Some(info) = self.registry.tlv_field(field.extension_type, field.field_type) else {
+                errors.push(format!("{}: Unknown field type {} for {} extension", label, field.field_type, extension.name));
+                continue;
+            };
+            if let Err(e) = tlv::check_session(extension, info, negotiated_extensions.as_deref(), message) {
+                errors.push(format!("{}: {}", label, e));
+                continue;
+            }
+            if values.iter().any(|(extension_type, field_type, _)| (*extension_type, *field_type) == (field.extension_type, field.field_type)) {
+                errors.push(format!("{}: duplicate {} field", label, info.name));
+                continue;
             }
+            let value = self
+                .tlv_value_format(field.extension_type, field.field_type, field.value_type.as_deref())
+                .and_then(|format| tlv_value_bytes(format, &field.value))
+                .and_then(|value| tlv::decode_field_value(info, &value).map(|_| value));
+            match value {
+                Ok(value) => values.push((field.extension_type, field.field_type, value)),
+                Err(e) => errors.push(format!("{}: {}", label, e)),
+            }
+        }
 
-            let value = bytes[offset + 5..offset + 5 + length as usize].to_vec();
-            
-            tlv_fields.push(TLVField {
-                extension_type,
-                field_type,
-                length,
-                value,
-            });
+        if !errors.is_empty() {
+            return BuildTLVListResponse {
+                tlv_fields: Vec::new(),
+                encoded_bytes: None,
+                total_length: None,
+                errors,
+            };
+        }
 
-            offset += 5 + length as usize;
+        values.sort_by_key(|(extension_type, field_type, _)| (*extension_type, *field_type));
+        let encoded: Vec<u8> = values
+            .iter()
+            .flat_map(|(extension_type, field_type, value)| tlv::encode_tlv(*extension_type, *field_type, value))
+            .collect();
+        BuildTLVListResponse {
+            tlv_fields: values
+                .iter()
+                .map(|(extension_type, field_type, value)| self.describe_tlv_field(*extension_type, *field_type, value))
+                .collect(),
+            total_length: Some(encoded.len()),
+            encoded_bytes: Some(hex::encode(encoded)),
+            errors,
         }
+    }
 
+    /// Parse concatenated TLV fields. Bytes that do not form a complete field
+    /// are returned as `trailing_bytes`; in strict mode they are also an error,
+    /// in lenient mode the complete fields before them still count as a success.
+    /// Reported offsets are counted from `base_offset`, the position of `bytes`
+    /// within the input they were taken from.
+    fn parse_tlv_fields_blocking(&self, bytes: Vec<u8>, lenient: bool, base_offset: usize) -> ParseTLVFieldsResponse {
+        let (fields, rest) = tlv::split_fields(&bytes, base_offset);
+        let tlv_fields = fields
+            .iter()
+            .map(|field| self.describe_tlv_field(field.extension_type, field.field_type, field.value))
+            .collect();
+        let (trailing_bytes, mut errors) = match rest {
+            Some((offset, error)) => (
+                Some(TrailingBytes {
+                    offset: base_offset + offset,
+                    hex: hex::encode(&bytes[offset..]),
+                }),
+                vec![error],
+            ),
+            None => (None, Vec::new()),
+        };
+        if lenient {
+            errors.clear();
+        }
         let parsed_successfully = errors.is_empty();
 
-        let response = ParseTLVFieldsResponse {
+        ParseTLVFieldsResponse {
             tlv_fields,
+            trailing_bytes,
             errors,
             parsed_successfully,
+        }
+    }
+
+    /// Annotate every byte of a message, interpreting the values of TLV fields
+    /// the registry defines. Other TLV values are left as hex.
+    fn annotate(
+        &self,
+        descriptor: &codec::MessageDescriptor,
+        bytes: &[u8],
+        framed: bool,
+    ) -> Result<Vec<ByteAnnotation>, codec::CodecError> {
+        let mut annotations = codec::annotate(descriptor, bytes, framed)?;
+        for index in 0..annotations.len() {
+            let Some(prefix) = annotations[index].field.strip_suffix(".value").map(str::to_string) else {
+                continue;
+            };
+            let header = |name: &str| {
+                let field = format!("{}.{}", prefix, name);
+                annotations.iter().find(|annotation| annotation.field == field).and_then(|annotation| annotation.value.as_u64())
+            };
+            let (Some(extension_type), Some(field_type)) = (header("extension_type"), header("field_type")) else {
+                continue;
+            };
+            let Some(field) = self.registry.tlv_field(extension_type as u16, field_type as u8) else {
+                continue;
+            };
+            let value = hex::decode(&annotations[index].hex).expect("annotations hold valid hex");
+            if let Ok(decoded) = tlv::decode_field_value(field, &value) {
+                annotations[index].data_type = format!("{} ({})", field.name, field.data_type);
+                annotations[index].value = decoded;
+            }
+        }
+        Ok(annotations)
+    }
+
+    /// Annotate a parsed TLV field with its registry entry. Unknown extensions
+    /// and field types are kept as raw bytes.
+    fn describe_tlv_field(&self, extension_type: u16, field_type: u8, value: &[u8]) -> ParsedTLVField {
+        let extension = self.registry.extension(extension_type);
+        let field = self.registry.tlv_field(extension_type, field_type);
+        let (decoded_value, error) = match (extension, field) {
+            (_, Some(field)) => match tlv::decode_field_value(field, value) {
+                Ok(decoded) => (Some(decoded), None),
+                Err(e) => (None, Some(e)),
+            },
+            (Some(extension), None) => {
+                (None, Some(format!("Unknown field type {} for {} extension", field_type, extension.name)))
+            }
+            (None, None) => (None, Some(format!("Unknown extension type 0x{:04x}", extension_type))),
         };
-        
-        serde_json::to_string_pretty(&response).unwrap()
+
+        ParsedTLVField {
+            extension_type,
+            field_type,
+            length: value.len() as u16,
+            value: hex::encode(value),
+            extension_name: extension.map(|extension| extension.name.clone()),
+            field_name: field.map(|field| field.name.clone()),
+            data_type: field.map(|field| field.data_type.clone()),
+            decoded_value,
+            known: field.is_some(),
+            valid: field.map(|_| error.is_none()),
+            error,
+        }
     }
 
-    fn validate_tlv_field_blocking(&self, extension_type: u16, field_type: u8, value: String) -> String {
+    /// Validate a TLV field, and with session context, that it may be sent:
+    /// its extension was negotiated and it is allowed on `message_type`.
+    fn validate_tlv_field_blocking(
+        &self,
+        extension_type: u16,
+        field_type: u8,
+        value: String,
+        value_type: Option<String>,
+        negotiated_extensions: Option<Vec<u16>>,
+        message_type: Option<String>,
+    ) -> ValidateTLVFieldResponse {
         let mut validation = TLVValidationResult {
             valid: true,
             error: None,
             field_info: Some(HashMap::new()),
         };
 
-        match extension_type {
-            0x0002 => {
-                if field_type == 0x01 {
-                    // Worker identity field validation
-                    if value.len() > 32 {
-                        validation.valid = false;
-                        validation.error = Some("Worker identity must be 32 bytes or less".to_string());
-                    } else {
-                        validation.field_info.as_mut().unwrap().insert(
-                            "field_name".to_string(), 
-                            json!("user_identity")
-                        );
-                        validation.field_info.as_mut().unwrap().insert(
-                            "max_length".to_string(), 
-                            json!(32)
-                        );
-                        validation.field_info.as_mut().unwrap().insert(
-                            "current_length".to_string(), 
-                            json!(value.len())
-                        );
-                        validation.field_info.as_mut().unwrap().insert(
-                            "encoding".to_string(), 
-                            json!("UTF-8")
-                        );
+        let message = match message_type.as_deref().map(resolve_message).transpose() {
+            Ok(message) => message,
+            Err(e) => {
+                validation.valid = false;
+                validation.error = Some(e.to_string());
+                return ValidateTLVFieldResponse { validation };
+            }
+        };
+
+        match self.registry.extension(extension_type) {
+            Some(extension) => match self.registry.tlv_field(extension_type, field_type) {
+                Some(field) => {
+                    let decoded = self
+                        .tlv_value_format(extension_type, field_type, value_type.as_deref())
+                        .and_then(|format| tlv_value_bytes(format, &value))
+                        .and_then(|bytes| tlv::decode_field_value(field, &bytes).map(|decoded| (bytes, decoded)))
+                        .and_then(|decoded| {
+                            tlv::check_session(extension, field, negotiated_extensions.as_deref(), message)
+                                .map(|_| decoded)
+                        });
+                    match decoded {
+                        Ok((bytes, decoded)) => {
+                            let field_info = validation.field_info.as_mut().unwrap();
+                            field_info.insert("field_name".to_string(), json!(field.name));
+                            field_info.insert("max_length".to_string(), json!(field.max_length));
+                            field_info.insert("current_length".to_string(), json!(bytes.len()));
+                            field_info.insert("data_type".to_string(), json!(field.data_type));
+                            field_info.insert("decoded_value".to_string(), decoded);
+                        }
+                        Err(e) => {
+                            validation.valid = false;
+                            validation.error = Some(e);
+                        }
                     }
-                } else {
+                }
+                None => {
                     validation.valid = false;
-                    validation.error = Some(format!("Unknown field type {} for Worker-Specific Hashrate Tracking extension", field_type));
+                    validation.error = Some(format!("Unknown field type {} for {} extension", field_type, extension.name));
                 }
             },
-            _ => {
+            None => {
                 validation.valid = false;
                 validation.error = Some(format!("Unknown extension type 0x{:04x}", extension_type));
             }
         }
 
-        let response = ValidateTLVFieldResponse { validation };
-        serde_json::to_string_pretty(&response).unwrap()
+        ValidateTLVFieldResponse { validation }
     }
 
-    fn generate_test_message_blocking(&self, message_type: String) -> String {
-        let message = match message_type.as_str() {
-            "SubmitSharesStandard" => {
-                json!({
-                    "message_type": "SubmitSharesStandard",
-                    "channel_id": 1,
-                    "sequence_number": 1,
-                    "job_id": 12345,
-                    "nonce": "0x12345678",
-                    "ntime": "0x5a123456",
-                    "version": "0x20000000",
-                    "description": "Standard share submission using mining_sv2 crate format"
-                })
+    fn generate_test_message_blocking(
+        &self,
+        message_type: String,
+        overrides: Option<Value>,
+        random: bool,
+        seed: Option<u64>,
+        encode: bool,
+    ) -> GenerateTestMessageResponse {
+        let error = |message: Option<Value>, seed: Option<u64>, error: String| GenerateTestMessageResponse {
+            message,
+            description: None,
+            encoded_bytes: None,
+            seed,
+            error: Some(error),
+        };
+
+        let descriptor = match resolve_message(&message_type) {
+            Ok(descriptor) => descriptor,
+            Err(e) => return error(None, None, e.to_string()),
+        };
+        if seed.is_some() && !random {
+            return error(None, None, "seed requires random: true".to_string());
+        }
+
+        // Random messages report their seed so a failing case can be reproduced
+        let (mut message, description, seed) = if random {
+            let seed = seed.unwrap_or_else(rand::random);
+            let mut rng = StdRng::seed_from_u64(seed);
+            (codec::random_message(descriptor, &mut rng), None, Some(seed))
+        } else {
+            match curated_test_message(descriptor.name) {
+                Some((description, message)) => (message, Some(description.to_string()), None),
+                None => (codec::default_message(descriptor), None, None),
+            }
+        };
+
+        if let Some(overrides) = overrides {
+            let Some(overrides) = overrides.as_object() else {
+                return error(None, seed, "Overrides must be a JSON object".to_string());
+            };
+            for (name, value) in overrides {
+                if !descriptor.fields.iter().any(|(field, _)| field == name) {
+                    return error(None, seed, format!("Unknown field {} for {}", name, descriptor.lookup_name()));
+                }
+                message[name] = value.clone();
+            }
+        }
+
+        let encoded_bytes = match encode {
+            true => match codec::encode(&descriptor.lookup_name(), &message) {
+                Ok(bytes) => Some(hex::encode(bytes)),
+                Err(e) => return error(Some(message), seed, format!("Failed to encode {}: {}", descriptor.lookup_name(), e)),
             },
-            "SetupConnection" => {
-                json!({
-                    "message_type": "SetupConnection",
-                    "protocol": "Stratum/2.0.0",
-                    "min_version": 2,
-                    "max_version": 2,
-                    "flags": 0,
-                    "endpoint_host": "pool.example.com",
-                    "endpoint_port": 3333,
-                    "vendor": "TestMiner",
-                    "hardware_version": "1.0",
-                    "firmware": "1.0.0",
-                    "device_id": "test-device-001",
-                    "description": "Connection setup using common_messages_sv2 format"
-                })
+            false => None,
+        };
+
+        GenerateTestMessageResponse {
+            message: Some(message),
+            description,
+            encoded_bytes,
+            seed,
+            error: None,
+        }
+    }
+
+    fn generate_test_vectors_blocking(&self, message_type: String) -> GenerateTestVectorsResponse {
+        match resolve_message(&message_type).and_then(|descriptor| Ok((descriptor, codec::test_vectors(descriptor)?))) {
+            Ok((descriptor, vectors)) => GenerateTestVectorsResponse {
+                message_type: Some(descriptor.lookup_name()),
+                vectors,
+                error: None,
             },
-            "NewTemplate" => {
-                json!({
-                    "message_type": "NewTemplate",
-                    "template_id": 12345,
-                    "future_template": false,
-                    "version": 0x20000000,
-                    "coinbase_tx_version": 1,
-                    "coinbase_prefix": "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff",
-                    "coinbase_tx_suffix": "ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000",
-                    "description": "Template distribution using template_distribution_sv2 format"
-                })
+            Err(e) => GenerateTestVectorsResponse {
+                message_type: None,
+                vectors: Vec::new(),
+                error: Some(e.to_string()),
             },
-            "DeclareTransaction" => {
-                json!({
-                    "message_type": "DeclareTransaction",
-                    "version": 1,
-                    "input_data_max_size": 1000,
-                    "tx_data": "0100000001a1b2c3d4e5f6...",
-                    "description": "Job declaration using job_declaration_sv2 format"
-                })
+        }
+    }
+
+    /// Encode a message, appending `tlvs` after its fields. With `frame`, the
+    /// complete frame is returned too, its msg_length covering the TLVs.
+    fn encode_message_blocking(
+        &self,
+        message: Value,
+        message_type: String,
+        verify: bool,
+        tlvs: Vec<CreateTLVFieldRequest>,
+        frame: bool,
+    ) -> EncodeMessageResponse {
+        let failure = |encoded_bytes: Option<&[u8]>, error: String| EncodeMessageResponse {
+            result: MessageEncodingResult {
+                success: false,
+                message_type: None,
+                encoded_bytes: encoded_bytes.map(hex::encode),
+                frame_bytes: None,
+                decoded_message: None,
+                tlvs: None,
+                annotations: None,
+                mismatches: None,
+                error: Some(error),
             },
-            _ => {
-                let response = GenerateTestMessageResponse {
-                    message: None,
-                    error: Some(format!("Unknown message type: {}. Available types: SubmitSharesStandard, SetupConnection, NewTemplate, DeclareTransaction", message_type)),
+        };
+
+        let (descriptor, mut bytes) = match codec::lookup(&message_type)
+            .and_then(|descriptor| Ok((descriptor, codec::encode(&message_type, &message)?)))
+        {
+            Ok(encoded) => encoded,
+            Err(e) => return failure(None, format!("Failed to encode {}: {}", message_type, e)),
+        };
+
+        let tlvs = match tlvs.is_empty() {
+            true => None,
+            false => {
+                let built = self.build_tlv_list_blocking(tlvs, None, Some(descriptor.lookup_name()));
+                let Some(encoded) = built.encoded_bytes else {
+                    return failure(Some(&bytes), format!("Invalid TLV fields: {}", built.errors.join("; ")));
                 };
-                return serde_json::to_string_pretty(&response).unwrap();
+                let tlv_bytes = hex::decode(encoded).expect("build_tlv_list returns valid hex");
+                let fields_length = bytes.len();
+                bytes.extend_from_slice(&tlv_bytes);
+                Some(self.parse_tlv_fields_blocking(tlv_bytes, false, fields_length))
             }
         };
 
-        let response = GenerateTestMessageResponse {
-            message: Some(message),
-            error: None,
+        // Decode what was just encoded and compare it field by field with the input
+        let (decoded_message, mismatches) = match verify {
+            true => match codec::decode(&message_type, &bytes) {
+                Ok(decoded) => {
+                    let mismatches = codec::round_trip_mismatches(descriptor, &message, &decoded);
+                    (Some(decoded), Some(mismatches))
+                }
+                Err(e) => return failure(Some(&bytes), format!("Encoded {} does not decode: {}", message_type, e)),
+            },
+            false => (None, None),
         };
-        
-        serde_json::to_string_pretty(&response).unwrap()
+
+        let frame_bytes = match frame {
+            true => match codec::encode_frame(0, descriptor.msg_type, descriptor.channel_msg, &bytes) {
+                Ok((_, frame)) => Some(hex::encode(frame)),
+                Err(e) => return failure(Some(&bytes), format!("Failed to frame {}: {}", message_type, e)),
+            },
+            false => None,
+        };
+
+        EncodeMessageResponse {
+            result: MessageEncodingResult {
+                success: true,
+                message_type: Some(descriptor.lookup_name()),
+                encoded_bytes: Some(hex::encode(&bytes)),
+                frame_bytes,
+                decoded_message,
+                tlvs,
+                annotations: None,
+                mismatches,
+                error: None,
+            },
+        }
     }
 
-    fn encode_message_blocking(&self, _message: Value, message_type: String) -> String {
-        // Note: Actual encoding would require proper type construction which has compatibility issues
-        let result = MessageEncodingResult {
-            success: false,
-            encoded_bytes: None,
-            decoded_message: None,
-            error: Some(format!(
-                "Message encoding for {} requires complex type conversions. Use the specific SV2 crate methods directly: binary_codec_sv2, codec_sv2", 
-                message_type
-            )),
+    fn decode_message_blocking(&self, bytes: Vec<u8>, message_type: Option<String>, annotate: bool) -> DecodeMessageResponse {
+        let framed = message_type.is_none();
+        let decoded = decode_input(&bytes, message_type.as_deref()).and_then(|(descriptor, decoded)| {
+            let annotations = match annotate {
+                true => Some(self.annotate(descriptor, &bytes, framed)?),
+                false => None,
+            };
+            // Bytes after the message fields are the TLV region; offsets are
+            // reported within the input, frame header included
+            let header_size = if framed { codec::FRAME_HEADER_SIZE } else { 0 };
+            let payload = &bytes[header_size..];
+            let fields_length = codec::fields_length(descriptor, payload)?;
+            let tlv_region = &payload[fields_length..];
+            let tlvs = (!tlv_region.is_empty())
+                .then(|| self.parse_tlv_fields_blocking(tlv_region.to_vec(), false, header_size + fields_length));
+            Ok((descriptor, decoded, tlvs, annotations))
+        });
+
+        let result = match decoded {
+            Ok((descriptor, decoded, tlvs, annotations)) => {
+                // The fields are still reported when the TLV region is malformed
+                let tlv_error = tlvs
+                    .as_ref()
+                    .filter(|tlvs| !tlvs.parsed_successfully)
+                    .map(|tlvs| format!("Invalid TLV fields: {}", tlvs.errors.join("; ")));
+                MessageEncodingResult {
+                    success: tlv_error.is_none(),
+                    message_type: Some(descriptor.lookup_name()),
+                    encoded_bytes: Some(hex::encode(&bytes)),
+                    frame_bytes: None,
+                    decoded_message: Some(decoded),
+                    tlvs,
+                    annotations,
+                    mismatches: None,
+                    error: tlv_error,
+                }
+            }
+            Err(e) => MessageEncodingResult {
+                success: false,
+                message_type: None,
+                encoded_bytes: Some(hex::encode(&bytes)),
+                frame_bytes: None,
+                decoded_message: None,
+                tlvs: None,
+                annotations: None,
+                mismatches: None,
+                error: Some(match &message_type {
+                    Some(message_type) => format!("Failed to decode {}: {}", message_type, e),
+                    None => format!("Failed to decode frame: {}", e),
+                }),
+            },
+        };
+
+        DecodeMessageResponse { result }
+    }
+
+    fn encode_frame_blocking(&self, message_type: String, payload: Vec<u8>, extension_type: u16, channel_msg: Option<bool>) -> EncodeFrameResponse {
+        let framed = codec::frame_message_type(&message_type).and_then(|(msg_type, default_channel_msg)| {
+            codec::encode_frame(extension_type, msg_type, channel_msg.unwrap_or(default_channel_msg), &payload)
+        });
+
+        match framed {
+            Ok((header, bytes)) => EncodeFrameResponse {
+                success: true,
+                header: Some(header),
+                frame_bytes: Some(hex::encode(&bytes)),
+                error: None,
+            },
+            Err(e) => EncodeFrameResponse {
+                success: false,
+                header: None,
+                frame_bytes: None,
+                error: Some(format!("Failed to frame {}: {}", message_type, e)),
+            },
+        }
+    }
+
+    fn decode_frame_blocking(&self, bytes: Vec<u8>) -> DecodeFrameResponse {
+        match codec::decode_frame(&bytes) {
+            Ok((header, payload)) => DecodeFrameResponse {
+                success: true,
+                actual_length: Some(payload.len()),
+                length_matches: Some(header.msg_length as usize == payload.len()),
+                header: Some(header),
+                payload: Some(hex::encode(payload)),
+                error: None,
+            },
+            Err(e) => DecodeFrameResponse {
+                success: false,
+                header: None,
+                actual_length: None,
+                length_matches: None,
+                payload: None,
+                error: Some(format!("Failed to decode frame: {}", e)),
+            },
+        }
+    }
+
+    fn decode_stream_blocking(&self, bytes: Vec<u8>) -> DecodeStreamResponse {
+        let mut frames = Vec::new();
+        let mut errors = Vec::new();
+        let mut offset = 0;
+
+        while offset < bytes.len() {
+            let (header, remaining) = match codec::decode_frame(&bytes[offset..]) {
+                Ok(parsed) => parsed,
+                Err(_) => {
+                    errors.push(format!("Insufficient bytes for frame header at offset {} (need {}, have {})",
+                        offset, codec::FRAME_HEADER_SIZE, bytes.len() - offset));
+                    break;
+                }
+            };
+
+            if header.msg_length as usize > remaining.len() {
+                errors.push(format!("Insufficient bytes for frame payload at offset {} (need {}, have {})",
+                    offset, header.msg_length, remaining.len()));
+                break;
+            }
+
+            let end = offset + codec::FRAME_HEADER_SIZE + header.msg_length as usize;
+            let frame = &bytes[offset..end];
+            let (message_type, decoded_message, error) = match codec::decode_frame_message(frame) {
+                Ok((descriptor, decoded)) => (Some(descriptor.lookup_name()), Some(decoded), None),
+                Err(e) => (None, None, Some(format!("Failed to decode frame: {}", e))),
+            };
+
+            frames.push(StreamFrame {
+                offset,
+                header,
+                message_type,
+                decoded_message,
+                payload: hex::encode(&frame[codec::FRAME_HEADER_SIZE..]),
+                error,
+            });
+
+            offset = end;
+        }
+
+        let (leftover_offset, leftover_bytes) = if offset < bytes.len() {
+            (Some(offset), Some(hex::encode(&bytes[offset..])))
+        } else {
+            (None, None)
         };
+        let parsed_successfully = errors.is_empty() && frames.iter().all(|frame| frame.error.is_none());
 
-        let response = EncodeMessageResponse { result };
-        serde_json::to_string_pretty(&response).unwrap()
+        DecodeStreamResponse {
+            frames,
+            leftover_offset,
+            leftover_bytes,
+            errors,
+            parsed_successfully,
+        }
     }
 
-    fn decode_message_blocking(&self, bytes: Vec<u8>, message_type: String) -> String {
-        // Note: Actual decoding would require proper type construction which has compatibility issues
-        let result = MessageEncodingResult {
-            success: false,
-            encoded_bytes: Some(hex::encode(&bytes)),
-            decoded_message: None,
-            error: Some(format!(
-                "Message decoding for {} requires complex type conversions. Use the specific SV2 crate methods directly: binary_codec_sv2, codec_sv2", 
-                message_type
-            )),
+    fn diff_messages_blocking(&self, left: Vec<u8>, right: Vec<u8>, message_type: Option<String>) -> DiffMessagesResponse {
+        let framed = message_type.is_none();
+        let annotated = |bytes: &[u8]| {
+            decode_input(bytes, message_type.as_deref()).and_then(|(descriptor, _)| {
+                Ok((descriptor.lookup_name(), self.annotate(descriptor, bytes, framed)?))
+            })
         };
 
-        let response = DecodeMessageResponse { result };
-        serde_json::to_string_pretty(&response).unwrap()
+        let ((left_type, left_fields), (right_type, right_fields)) = match (annotated(&left), annotated(&right)) {
+            (Ok(left), Ok(right)) => (left, right),
+            (left_result, right_result) => {
+                let errors: Vec<String> = [("left", left_result.err()), ("right", right_result.err())]
+                    .into_iter()
+                    .filter_map(|(side, error)| error.map(|e| format!("Failed to decode {}: {}", side, e)))
+                    .collect();
+                return DiffMessagesResponse {
+                    success: false,
+                    left_message_type: None,
+                    right_message_type: None,
+                    identical: left == right,
+                    differences: Vec::new(),
+                    error: Some(errors.join("; ")),
+                };
+            }
+        };
+
+        // Fields are matched by path, so a changed length prefix shows up next to the bytes it covers
+        let mut differences = Vec::new();
+        for left_field in &left_fields {
+            let right_field = right_fields.iter().find(|field| field.field == left_field.field);
+            if right_field.map(|field| &field.hex) != Some(&left_field.hex) {
+                differences.push(FieldDiff {
+                    field: left_field.field.clone(),
+                    left: Some(left_field.clone()),
+                    right: right_field.cloned(),
+                });
+            }
+        }
+        for right_field in &right_fields {
+            if !left_fields.iter().any(|field| field.field == right_field.field) {
+                differences.push(FieldDiff {
+                    field: right_field.field.clone(),
+                    left: None,
+                    right: Some(right_field.clone()),
+                });
+            }
+        }
+
+        DiffMessagesResponse {
+            success: true,
+            left_message_type: Some(left_type),
+            right_message_type: Some(right_type),
+            identical: left == right,
+            differences,
+            error: None,
+        }
     }
 
     fn demonstrate_advanced_features_blocking(&self) -> String {
@@ -698,4 +1159,295 @@ impl StratumV2MCPServer {
 
         serde_json::to_string_pretty(&buffer_info).unwrap()
     }
-} 
\ No newline at end of file
+}
+
+/// Value bytes of a TLV field, which must fit the U16 length.
+fn tlv_value_bytes(format: tlv::TlvValueFormat, value: &str) -> Result<Vec<u8>, String> {
+    let bytes = format
+        .encode(value)
+        .map_err(|e| format!("Invalid {} value: {}", format.name(), e))?;
+    if bytes.len() > 65535 {
+        return Err(format!("Value too long: {} bytes (max 65535)", bytes.len()));
+    }
+    Ok(bytes)
+}
+
+/// Resolve a message name or numeric msg_type; msg_types are unique across
+/// subprotocols, so a number identifies a single message.
+fn resolve_message(message_type: &str) -> Result<&'static codec::MessageDescriptor, codec::CodecError> {
+    codec::frame_message_type(message_type).and_then(|(msg_type, _)| codec::lookup_by_type(0, msg_type))
+}
+
+/// Decode a payload of the named message type, or a complete frame whose header
+/// names the message when no type is given.
+fn decode_input(
+    bytes: &[u8],
+    message_type: Option<&str>,
+) -> Result<(&'static codec::MessageDescriptor, Value), codec::CodecError> {
+    match message_type {
+        Some(message_type) => {
+            let descriptor = codec::lookup(message_type)?;
+            Ok((descriptor, codec::decode(message_type, bytes)?))
+        }
+        None => codec::decode_frame_message(bytes),
+    }
+}
+
+/// Hand-written samples with realistic values for commonly used messages,
+/// with a description of each.
+fn curated_test_message(name: &str) -> Option<(&'static str, Value)> {
+    match name {
+        "SubmitSharesStandard" => Some((
+            "Standard share submission using mining_sv2 crate format",
+            json!({
+                "channel_id": 1,
+                "sequence_number": 1,
+                "job_id": 12345,
+                "nonce": "0x12345678",
+                "ntime": "0x5a123456",
+                "version": "0x20000000"
+            }),
+        )),
+        "SetupConnection" => Some((
+            "Connection setup using common_messages_sv2 format",
+            json!({
+                "protocol": 0,
+                "min_version": 2,
+                "max_version": 2,
+                "flags": 0,
+                "endpoint_host": "pool.example.com",
+                "endpoint_port": 3333,
+                "vendor": "TestMiner",
+                "hardware_version": "1.0",
+                "firmware": "1.0.0",
+                "device_id": "test-device-001"
+            }),
+        )),
+        "NewTemplate" => Some((
+            "Template distribution using template_distribution_sv2 format",
+            json!({
+                "template_id": 12345,
+                "future_template": false,
+                "version": 0x20000000,
+                "coinbase_tx_version": 1,
+                "coinbase_prefix": "03a08601",
+                "coinbase_tx_input_sequence": 0xffffffffu32,
+                "coinbase_tx_value_remaining": 5000000000u64,
+                "coinbase_tx_outputs_count": 1,
+                "coinbase_tx_outputs": "0000000000000000266a24aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf9",
+                "coinbase_tx_locktime": 0,
+                "merkle_path": []
+            }),
+        )),
+        "AllocateMiningJobToken" => Some((
+            "Job token request using job_declaration_sv2 format",
+            json!({
+                "user_identifier": "test-miner",
+                "request_id": 1
+            }),
+        )),
+        "AllocateMiningJobTokenSuccess" => Some((
+            "Job token allocation using job_declaration_sv2 format",
+            json!({
+                "request_id": 1,
+                "mining_job_token": "00010203",
+                "coinbase_outputs": "0000000000000000160014ebe1b7dcc293ccaa0ee743a86f89df8258c208fc"
+            }),
+        )),
+        "DeclareMiningJob" => Some((
+            "Custom job declaration using job_declaration_sv2 format",
+            json!({
+                "request_id": 2,
+                "mining_job_token": "00010203",
+                "version": 0x20000000,
+                "coinbase_prefix": "02000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0803a08601",
+                "coinbase_suffix": "ffffffff0100f2052a01000000160014ebe1b7dcc293ccaa0ee743a86f89df8258c208fc00000000",
+                "tx_ids_list": ["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"],
+                "excess_data": ""
+            }),
+        )),
+        "DeclareMiningJobSuccess" => Some((
+            "Job declaration acceptance using job_declaration_sv2 format",
+            json!({
+                "request_id": 2,
+                "new_mining_job_token": "04050607"
+            }),
+        )),
+        "DeclareMiningJobError" => Some((
+            "Job declaration rejection using job_declaration_sv2 format",
+            json!({
+                "request_id": 2,
+                "error_code": "invalid-mining-job-token",
+                "error_details": ""
+            }),
+        )),
+        "ProvideMissingTransactions" => Some((
+            "Missing transaction request using job_declaration_sv2 format",
+            json!({
+                "request_id": 2,
+                "unknown_tx_position_list": [0]
+            }),
+        )),
+        "ProvideMissingTransactionsSuccess" => Some((
+            "Missing transaction data using job_declaration_sv2 format",
+            json!({
+                "request_id": 2,
+                "transaction_list": ["01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0704ffff001d0104ffffffff0100f2052a0100000043410496b538e853519c726a2c91e61ec11600ae1390813a627c66fb8be7947be63c52da7589379515d4e0a604f8141781e62294721166bf621e73a82cbf2342c858eeac00000000"]
+            }),
+        )),
+        "PushSolution" => Some((
+            "Block solution using job_declaration_sv2 format",
+            json!({
+                "extranonce": "0000000000000001",
+                "prev_hash": "000000000000000000024bead8df69990852c202db0e0097c1a12ea637d7e96d",
+                "ntime": "0x5a123456",
+                "nonce": "0x12345678",
+                "nbits": "0x17034219",
+                "version": "0x20000000"
+            }),
+        )),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builtin registry plus an unrestricted extension with two fields.
+    fn server_with_payout_hints() -> StratumV2MCPServer {
+        let mut registry = ProtocolRegistry::builtin();
+        let field = |field_type: u8, name: &str| types::TLVFieldInfo {
+            field_type,
+            name: name.to_string(),
+            data_type: "bytes".to_string(),
+            max_length: None,
+            description: String::new(),
+            messages: Vec::new(),
+        };
+        registry.merge_extension(types::ExtensionInfo {
+            extension_type: 0x4001,
+            name: "Payout Hints".to_string(),
+            description: String::new(),
+            negotiation_required: true,
+            messages: Vec::new(),
+            tlv_fields: vec![field(0x01, "payout_address"), field(0x02, "payout_split")],
+        });
+        StratumV2MCPServer::with_registry(registry)
+    }
+
+    fn tlv(extension_type: u16, field_type: u8, value: &str) -> CreateTLVFieldRequest {
+        CreateTLVFieldRequest {
+            extension_type,
+            field_type,
+            value: value.to_string(),
+            value_type: None,
+        }
+    }
+
+    #[test]
+    fn build_tlv_list_sorts_by_extension_then_field() {
+        let server = server_with_payout_hints();
+        let built = server.build_tlv_list_blocking(
+            vec![tlv(0x4001, 0x02, "bb"), tlv(0x0002, 0x01, "w"), tlv(0x4001, 0x01, "aa")],
+            Some(vec![0x0002, 0x4001]),
+            Some("SubmitSharesExtended".to_string()),
+        );
+        assert!(built.errors.is_empty(), "{:?}", built.errors);
+        let order: Vec<(u16, u8)> = built
+            .tlv_fields
+            .iter()
+            .map(|field| (field.extension_type, field.field_type))
+            .collect();
+        assert_eq!(order, [(0x0002, 0x01), (0x4001, 0x01), (0x4001, 0x02)]);
+        assert_eq!(built.encoded_bytes.as_deref(), Some("0200010100770140010100aa0140020100bb"));
+        assert_eq!(built.total_length, Some(18));
+    }
+
+    #[test]
+    fn build_tlv_list_rejects_duplicates() {
+        let server = server_with_payout_hints();
+        let built = server.build_tlv_list_blocking(vec![tlv(0x4001, 0x01, "aa"), tlv(0x4001, 0x01, "bb")], None, None);
+        assert!(built.encoded_bytes.is_none());
+        assert_eq!(built.errors.len(), 1);
+        assert!(built.errors[0].contains("duplicate payout_address field"), "{}", built.errors[0]);
+    }
+
+    #[test]
+    fn build_tlv_list_rejects_extensions_that_were_not_negotiated() {
+        let server = server_with_payout_hints();
+        let built = server.build_tlv_list_blocking(
+            vec![tlv(0x4001, 0x01, "aa"), tlv(0x0002, 0x01, "w")],
+            Some(vec![0x4001]),
+            None,
+        );
+        assert!(built.encoded_bytes.is_none());
+        assert_eq!(built.errors.len(), 1);
+        assert!(built.errors[0].contains("was not negotiated"), "{}", built.errors[0]);
+    }
+
+    #[test]
+    fn build_tlv_list_rejects_fields_not_allowed_on_the_message() {
+        let server = server_with_payout_hints();
+        let built = server.build_tlv_list_blocking(
+            vec![tlv(0x0002, 0x01, "w")],
+            None,
+            Some("SubmitSharesStandard".to_string()),
+        );
+        assert!(built.encoded_bytes.is_none());
+        assert_eq!(built.errors.len(), 1);
+        assert!(
+            built.errors[0].contains("user_identity is only valid on SubmitSharesExtended"),
+            "{}",
+            built.errors[0]
+        );
+    }
+
+    #[test]
+    fn parse_tlv_fields_reports_bytes_shorter_than_a_header() {
+        let server = StratumV2MCPServer::new();
+        let field = tlv::encode_tlv(0x0002, 0x01, b"worker123");
+        for extra in 1..=4 {
+            let mut bytes = field.clone();
+            bytes.extend(vec![0xaa; extra]);
+
+            let strict = server.parse_tlv_fields_blocking(bytes.clone(), false, 0);
+            assert_eq!(strict.tlv_fields.len(), 1);
+            assert_eq!(strict.tlv_fields[0].decoded_value, Some(json!("worker123")));
+            assert!(!strict.parsed_successfully);
+            assert_eq!(strict.errors.len(), 1);
+            let trailing = strict.trailing_bytes.expect("trailing bytes are reported");
+            assert_eq!(trailing.offset, field.len());
+            assert_eq!(trailing.hex, hex::encode(vec![0xaa; extra]));
+
+            let lenient = server.parse_tlv_fields_blocking(bytes, true, 0);
+            assert_eq!(lenient.tlv_fields.len(), 1);
+            assert!(lenient.parsed_successfully);
+            assert!(lenient.errors.is_empty());
+            assert_eq!(lenient.trailing_bytes.map(|trailing| trailing.offset), Some(field.len()));
+        }
+    }
+
+    #[test]
+    fn curated_test_messages_match_their_schema() {
+        for descriptor in codec::messages() {
+            if let Some((_, message)) = curated_test_message(descriptor.name) {
+                let validator = jsonschema::validator_for(&codec::json_schema(descriptor)).unwrap();
+                assert!(validator.is_valid(&message), "{}", descriptor.name);
+            }
+        }
+    }
+
+    #[test]
+    fn generate_test_message_rejects_seed_without_random() {
+        let server = StratumV2MCPServer::new();
+        let response = server.generate_test_message_blocking("SetupConnection".to_string(), None, false, Some(42), false);
+        assert!(response.message.is_none());
+        assert_eq!(response.error.as_deref(), Some("seed requires random: true"));
+
+        let first = server.generate_test_message_blocking("SetupConnection".to_string(), None, true, Some(42), false);
+        let second = server.generate_test_message_blocking("SetupConnection".to_string(), None, true, Some(42), false);
+        assert_eq!(first.seed, Some(42));
+        assert_eq!(first.message, second.message);
+    }
+}
@@ -1,11 +1,19 @@
+use std::path::PathBuf;
+
+use stratum_v2_mcp_server::registry::ProtocolRegistry;
+use stratum_v2_mcp_server::types::CreateTLVFieldRequest;
 use stratum_v2_mcp_server::StratumV2MCPServer;
-use clap::{Parser, Subcommand, CommandFactory};
+use clap::{Parser, Subcommand};
 use serde_json::Value;
 
 #[derive(Parser)]
 #[command(name = "stratum-v2-mcp-server")]
 #[command(about = "MCP server for working with Stratum V2 protocol specification")]
 struct Cli {
+    /// JSON or TOML file with additional extension and TLV field definitions
+    #[arg(long, global = true, value_name = "FILE")]
+    registry: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -16,8 +24,27 @@ enum Commands {
     AnalyzeProtocol,
     
     /// List all supported message types
-    ListMessageTypes,
+    ListMessageTypes {
+        /// Only list messages of this subprotocol (common, mining, job_declaration, template_distribution)
+        #[arg(long)]
+        subprotocol: Option<String>,
+        /// Only list messages sent in this direction (client_to_server, server_to_client)
+        #[arg(long)]
+        direction: Option<String>,
+    },
     
+    /// Get the complete definition of a message by name or msg_type
+    GetMessageInfo {
+        #[arg(value_name = "MESSAGE_TYPE")]
+        message_type: String,
+    },
+
+    /// Print the JSON Schema of a message's JSON form
+    GetMessageSchema {
+        #[arg(value_name = "MESSAGE_TYPE")]
+        message_type: String,
+    },
+
     /// List all supported extensions
     ListExtensions,
     
@@ -35,12 +62,32 @@ enum Commands {
         field_type: u8,
         #[arg(value_name = "VALUE")]
         value: String,
+        /// How to read VALUE: utf8, hex, base64, u8, u16, u32, u64 or integer
+        /// (default: the field's registered data type)
+        #[arg(long, value_name = "TYPE")]
+        value_type: Option<String>,
+    },
+    
+    /// Build a TLV block from a JSON array of fields
+    BuildTlvList {
+        /// JSON array of {"extension_type", "field_type", "value", "value_type"?} objects
+        #[arg(value_name = "FIELDS_JSON")]
+        fields: String,
+        /// Comma-separated extension types agreed via RequestExtensions.Success
+        #[arg(long, value_name = "TYPES", value_delimiter = ',')]
+        negotiated: Option<Vec<u16>>,
+        /// Message the block is appended to, e.g. SubmitSharesExtended
+        #[arg(long)]
+        message_type: Option<String>,
     },
     
     /// Parse TLV fields from a hex string
     ParseTlvFields {
         #[arg(value_name = "HEX_BYTES")]
         hex_bytes: String,
+        /// Report incomplete trailing bytes without failing the parse
+        #[arg(long)]
+        lenient: bool,
     },
     
     /// Validate a TLV field
@@ -51,12 +98,40 @@ enum Commands {
         field_type: u8,
         #[arg(value_name = "VALUE")]
         value: String,
+        /// How to read VALUE: utf8, hex, base64, u8, u16, u32, u64 or integer
+        /// (default: the field's registered data type)
+        #[arg(long, value_name = "TYPE")]
+        value_type: Option<String>,
+        /// Comma-separated extension types agreed via RequestExtensions.Success
+        #[arg(long, value_name = "TYPES", value_delimiter = ',')]
+        negotiated: Option<Vec<u16>>,
+        /// Message the field is attached to, e.g. SubmitSharesExtended
+        #[arg(long)]
+        message_type: Option<String>,
     },
     
     /// Generate a test message
     GenerateTestMessage {
         #[arg(value_name = "MESSAGE_TYPE")]
         message_type: String,
+        /// JSON object of field values replacing the generated ones
+        #[arg(long, value_name = "JSON")]
+        overrides: Option<String>,
+        /// Fill every field with a random value within its type's bounds
+        #[arg(long)]
+        random: bool,
+        /// Seed for --random, to reproduce a previous message
+        #[arg(long, requires = "random")]
+        seed: Option<u64>,
+        /// Also print the encoded message payload
+        #[arg(long)]
+        encode: bool,
+    },
+    
+    /// Generate boundary and malformed encodings of a message with their expected decoder outcome
+    GenerateTestVectors {
+        #[arg(value_name = "MESSAGE_TYPE")]
+        message_type: String,
     },
     
     /// Encode a JSON message to binary
@@ -65,16 +140,71 @@ enum Commands {
         message_type: String,
         #[arg(value_name = "JSON_MESSAGE")]
         json_message: String,
+        /// Decode the result again and report fields that did not survive the round trip
+        #[arg(long)]
+        verify: bool,
+        /// JSON array of TLV fields to append, as accepted by build-tlv-list
+        #[arg(long, value_name = "FIELDS_JSON")]
+        tlvs: Option<String>,
+        /// Also print the complete frame
+        #[arg(long)]
+        frame: bool,
     },
     
-    /// Decode binary data to JSON
+    /// Decode binary data to JSON; pass only a complete frame to detect the message type
     DecodeMessage {
+        /// Message type of HEX_BYTES; when given alone, the hex of a complete
+        /// frame whose header names the message
+        #[arg(value_name = "MESSAGE_TYPE")]
+        message_type: String,
+        /// Message payload as hex, without a frame header
+        #[arg(value_name = "HEX_BYTES")]
+        hex_bytes: Option<String>,
+        /// Also print the offset, length, raw bytes and value of every field
+        #[arg(long)]
+        annotate: bool,
+    },
+
+    /// Compare two messages field by field (frames unless --message-type is given)
+    DiffMessages {
+        #[arg(value_name = "LEFT_HEX")]
+        left_hex: String,
+        #[arg(value_name = "RIGHT_HEX")]
+        right_hex: String,
+        /// Message type of both inputs when they are bare payloads
+        #[arg(long)]
+        message_type: Option<String>,
+    },
+
+    /// Wrap an encoded message payload in an SV2 frame header
+    EncodeFrame {
         #[arg(value_name = "MESSAGE_TYPE")]
         message_type: String,
+        #[arg(value_name = "PAYLOAD_HEX")]
+        payload: String,
+        /// Extension type for the header
+        #[arg(long, default_value_t = 0)]
+        extension_type: u16,
+        /// Override the channel_msg bit (defaults to the message's own setting)
+        #[arg(long)]
+        channel_msg: Option<bool>,
+    },
+
+    /// Split an SV2 frame into its header fields and payload
+    DecodeFrame {
         #[arg(value_name = "HEX_BYTES")]
         hex_bytes: String,
     },
 
+    /// Decode a capture of back-to-back SV2 frames
+    DecodeStream {
+        #[arg(value_name = "HEX_BYTES", required_unless_present = "file")]
+        hex_bytes: Option<String>,
+        /// Read the capture (hex text or raw bytes) from a file instead
+        #[arg(long, conflicts_with = "hex_bytes")]
+        file: Option<PathBuf>,
+    },
+
     /// Demonstrate advanced Stratum V2 features using official crates
     DemonstrateAdvancedFeatures,
 
@@ -91,7 +221,11 @@ enum Commands {
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let server = StratumV2MCPServer::new();
+    let mut registry = ProtocolRegistry::builtin();
+    if let Some(path) = &cli.registry {
+        registry.merge_file(path)?;
+    }
+    let server = StratumV2MCPServer::with_registry(registry);
 
     // CLI mode
     match &cli.command {
@@ -100,11 +234,21 @@ async fn main() -> anyhow::Result<()> {
             println!("{}", result);
         },
         
-        Some(Commands::ListMessageTypes) => {
-            let result = server.list_message_types().await;
+        Some(Commands::ListMessageTypes { subprotocol, direction }) => {
+            let result = server.list_message_types(subprotocol.clone(), direction.clone()).await;
             println!("{}", result);
         },
         
+        Some(Commands::GetMessageInfo { message_type }) => {
+            let result = server.get_message_info(message_type.clone()).await;
+            println!("{}", result);
+        },
+
+        Some(Commands::GetMessageSchema { message_type }) => {
+            let result = server.get_message_schema(message_type.clone()).await;
+            println!("{}", result);
+        },
+
         Some(Commands::ListExtensions) => {
             let result = server.list_extensions().await;
             println!("{}", result);
@@ -115,36 +259,97 @@ async fn main() -> anyhow::Result<()> {
             println!("{}", result);
         },
         
-        Some(Commands::CreateTlvField { extension_type, field_type, value }) => {
-            let result = server.create_tlv_field(*extension_type, *field_type, value.clone()).await;
+        Some(Commands::CreateTlvField { extension_type, field_type, value, value_type }) => {
+            let result = server
+                .create_tlv_field(*extension_type, *field_type, value.clone(), value_type.clone())
+                .await;
+            println!("{}", result);
+        },
+        
+        Some(Commands::BuildTlvList { fields, negotiated, message_type }) => {
+            let fields: Vec<CreateTLVFieldRequest> = serde_json::from_str(fields)?;
+            let result = server.build_tlv_list(fields, negotiated.clone(), message_type.clone()).await;
             println!("{}", result);
         },
         
-        Some(Commands::ParseTlvFields { hex_bytes }) => {
+        Some(Commands::ParseTlvFields { hex_bytes, lenient }) => {
             let bytes = hex::decode(hex_bytes)?;
-            let result = server.parse_tlv_fields(bytes).await;
+            let result = server.parse_tlv_fields(bytes, *lenient).await;
+            println!("{}", result);
+        },
+        
+        Some(Commands::ValidateTlvField { extension_type, field_type, value, value_type, negotiated, message_type }) => {
+            let result = server
+                .validate_tlv_field(
+                    *extension_type,
+                    *field_type,
+                    value.clone(),
+                    value_type.clone(),
+                    negotiated.clone(),
+                    message_type.clone(),
+                )
+                .await;
             println!("{}", result);
         },
         
-        Some(Commands::ValidateTlvField { extension_type, field_type, value }) => {
-            let result = server.validate_tlv_field(*extension_type, *field_type, value.clone()).await;
+        Some(Commands::GenerateTestMessage { message_type, overrides, random, seed, encode }) => {
+            let overrides = overrides.as_deref().map(serde_json::from_str::<Value>).transpose()?;
+            let result = server.generate_test_message(message_type.clone(), overrides, *random, *seed, *encode).await;
             println!("{}", result);
         },
         
-        Some(Commands::GenerateTestMessage { message_type }) => {
-            let result = server.generate_test_message(message_type.clone()).await;
+        Some(Commands::GenerateTestVectors { message_type }) => {
+            let result = server.generate_test_vectors(message_type.clone()).await;
             println!("{}", result);
         },
         
-        Some(Commands::EncodeMessage { message_type, json_message }) => {
+        Some(Commands::EncodeMessage { message_type, json_message, verify, tlvs, frame }) => {
             let message: Value = serde_json::from_str(json_message)?;
-            let result = server.encode_message(message, message_type.clone()).await;
+            let tlvs: Vec<CreateTLVFieldRequest> = match tlvs {
+                Some(tlvs) => serde_json::from_str(tlvs)?,
+                None => Vec::new(),
+            };
+            let result = server.encode_message(message, message_type.clone(), *verify, tlvs, *frame).await;
             println!("{}", result);
         },
         
-        Some(Commands::DecodeMessage { message_type, hex_bytes }) => {
+        Some(Commands::DecodeMessage { message_type, hex_bytes, annotate }) => {
+            // A single argument is a frame whose header names the message
+            let (message_type, hex_bytes) = match hex_bytes {
+                Some(hex_bytes) => (Some(message_type.clone()), hex_bytes),
+                None => (None, message_type),
+            };
             let bytes = hex::decode(hex_bytes)?;
-            let result = server.decode_message(bytes, message_type.clone()).await;
+            let result = server.decode_message(bytes, message_type, *annotate).await;
+            println!("{}", result);
+        },
+
+        Some(Commands::DiffMessages { left_hex, right_hex, message_type }) => {
+            let left = hex::decode(left_hex)?;
+            let right = hex::decode(right_hex)?;
+            let result = server.diff_messages(left, right, message_type.clone()).await;
+            println!("{}", result);
+        },
+
+        Some(Commands::EncodeFrame { message_type, payload, extension_type, channel_msg }) => {
+            let payload = hex::decode(payload)?;
+            let result = server.encode_frame(message_type.clone(), payload, *extension_type, *channel_msg).await;
+            println!("{}", result);
+        },
+
+        Some(Commands::DecodeFrame { hex_bytes }) => {
+            let bytes = hex::decode(hex_bytes)?;
+            let result = server.decode_frame(bytes).await;
+            println!("{}", result);
+        },
+
+        Some(Commands::DecodeStream { hex_bytes, file }) => {
+            let bytes = match (hex_bytes, file) {
+                (_, Some(file)) => stratum_v2_mcp_server::capture::read_file(file)?,
+                (Some(hex_bytes), None) => stratum_v2_mcp_server::capture::parse_hex(hex_bytes)?,
+                (None, None) => unreachable!("clap requires HEX_BYTES or --file"),
+            };
+            let result = server.decode_stream(bytes).await;
             println!("{}", result);
         },
 
@@ -170,7 +375,6 @@ async fn main() -> anyhow::Result<()> {
 
         None => {
             // MCP mode - handle JSON-RPC over stdin/stdout
-            let server = StratumV2MCPServer::new();
             server.run_mcp_server().await?;
         }
     }
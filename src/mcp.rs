@@ -0,0 +1,297 @@
+use rmcp::{
+    handler::server::wrapper::{Json, Parameters},
+    model::{Implementation, ServerCapabilities, ServerInfo},
+    tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler,
+};
+
+use crate::types::*;
+use crate::StratumV2MCPServer;
+
+#[tool_router]
+impl StratumV2MCPServer {
+    #[tool(
+        name = "analyze_protocol_spec",
+        description = "Analyze the Stratum V2 protocol specification"
+    )]
+    async fn analyze_protocol_spec_tool(&self) -> Json<ProtocolSpec> {
+        Json(self.analyze_protocol_spec_blocking())
+    }
+
+    #[tool(
+        name = "list_message_types",
+        description = "List Stratum V2 messages with their msg_type, subprotocol, direction, channel_msg bit and fields, optionally filtered by subprotocol and direction"
+    )]
+    async fn list_message_types_tool(
+        &self,
+        Parameters(request): Parameters<ListMessageTypesRequest>,
+    ) -> Json<ListMessageTypesResponse> {
+        Json(self.list_message_types_blocking(request.subprotocol, request.direction))
+    }
+
+    #[tool(
+        name = "get_message_info",
+        description = "Get the complete definition of a message by name or msg_type: subprotocol, direction, channel_msg bit and every field with its SV2 data type and encoded size range"
+    )]
+    async fn get_message_info_tool(
+        &self,
+        Parameters(request): Parameters<GetMessageInfoRequest>,
+    ) -> Json<GetMessageInfoResponse> {
+        Json(self.get_message_info_blocking(request.message_type))
+    }
+
+    #[tool(
+        name = "get_message_schema",
+        description = "Get a JSON Schema describing the JSON accepted by encode_message for a message type"
+    )]
+    async fn get_message_schema_tool(
+        &self,
+        Parameters(request): Parameters<GetMessageSchemaRequest>,
+    ) -> Json<GetMessageSchemaResponse> {
+        Json(self.get_message_schema_blocking(request.message_type))
+    }
+
+    #[tool(
+        name = "list_extensions",
+        description = "List all supported protocol extensions"
+    )]
+    async fn list_extensions_tool(&self) -> Json<ListExtensionsResponse> {
+        Json(self.list_extensions_blocking())
+    }
+
+    #[tool(
+        name = "get_extension_info",
+        description = "Get detailed information about a specific extension"
+    )]
+    async fn get_extension_info_tool(
+        &self,
+        Parameters(request): Parameters<GetExtensionInfoRequest>,
+    ) -> Json<GetExtensionInfoResponse> {
+        Json(self.get_extension_info_blocking(request.extension_type))
+    }
+
+    #[tool(
+        name = "create_tlv_field",
+        description = "Create a TLV field for a specific extension from a string, hex, base64 or integer value"
+    )]
+    async fn create_tlv_field_tool(
+        &self,
+        Parameters(request): Parameters<CreateTLVFieldRequest>,
+    ) -> Json<CreateTLVFieldResponse> {
+        Json(self.create_tlv_field_blocking(
+            request.extension_type,
+            request.field_type,
+            request.value,
+            request.value_type,
+        ))
+    }
+
+    #[tool(
+        name = "build_tlv_list",
+        description = "Build the TLV block appended to a message from several fields, validated against the registry and negotiated extensions"
+    )]
+    async fn build_tlv_list_tool(
+        &self,
+        Parameters(request): Parameters<BuildTLVListRequest>,
+    ) -> Json<BuildTLVListResponse> {
+        Json(self.build_tlv_list_blocking(request.fields, request.negotiated_extensions, request.message_type))
+    }
+
+    #[tool(
+        name = "parse_tlv_fields",
+        description = "Parse TLV fields from hex-encoded bytes; incomplete trailing bytes are an error unless lenient"
+    )]
+    async fn parse_tlv_fields_tool(
+        &self,
+        Parameters(request): Parameters<ParseTLVFieldsRequest>,
+    ) -> Result<Json<ParseTLVFieldsResponse>, McpError> {
+        let bytes = decode_hex("hex_bytes", &request.hex_bytes)?;
+        Ok(Json(self.parse_tlv_fields_blocking(bytes, request.lenient, 0)))
+    }
+
+    #[tool(
+        name = "validate_tlv_field",
+        description = "Validate a TLV field against its extension definition"
+    )]
+    async fn validate_tlv_field_tool(
+        &self,
+        Parameters(request): Parameters<ValidateTLVFieldRequest>,
+    ) -> Json<ValidateTLVFieldResponse> {
+        Json(self.validate_tlv_field_blocking(
+            request.extension_type,
+            request.field_type,
+            request.value,
+            request.value_type,
+            request.negotiated_extensions,
+            request.message_type,
+        ))
+    }
+
+    #[tool(
+        name = "generate_test_message",
+        description = "Generate a test message of any type, with optional field overrides, seeded random values and the encoded bytes"
+    )]
+    async fn generate_test_message_tool(
+        &self,
+        Parameters(request): Parameters<GenerateTestMessageRequest>,
+    ) -> Json<GenerateTestMessageResponse> {
+        Json(self.generate_test_message_blocking(
+            request.message_type,
+            request.overrides,
+            request.random,
+            request.seed,
+            request.encode,
+        ))
+    }
+
+    #[tool(
+        name = "generate_test_vectors",
+        description = "Generate labeled boundary and malformed encodings of a message type, each with the expected decoder outcome"
+    )]
+    async fn generate_test_vectors_tool(
+        &self,
+        Parameters(request): Parameters<GenerateTestVectorsRequest>,
+    ) -> Json<GenerateTestVectorsResponse> {
+        Json(self.generate_test_vectors_blocking(request.message_type))
+    }
+
+    #[tool(
+        name = "encode_message",
+        description = "Encode a JSON message to binary with optional TLV fields and frame, optionally verifying it survives a decode round trip"
+    )]
+    async fn encode_message_tool(
+        &self,
+        Parameters(request): Parameters<EncodeMessageRequest>,
+    ) -> Json<EncodeMessageResponse> {
+        Json(self.encode_message_blocking(
+            request.message,
+            request.message_type,
+            request.verify,
+            request.tlvs,
+            request.frame,
+        ))
+    }
+
+    #[tool(
+        name = "decode_message",
+        description = "Decode binary data to JSON, parsing TLV fields after the message; without message_type, decode a complete frame using its header"
+    )]
+    async fn decode_message_tool(
+        &self,
+        Parameters(request): Parameters<DecodeMessageRequest>,
+    ) -> Result<Json<DecodeMessageResponse>, McpError> {
+        let bytes = decode_hex("hex_bytes", &request.hex_bytes)?;
+        Ok(Json(self.decode_message_blocking(bytes, request.message_type, request.annotate)))
+    }
+
+    #[tool(
+        name = "diff_messages",
+        description = "Compare two SV2 messages or frames field by field, with byte offsets for each difference"
+    )]
+    async fn diff_messages_tool(
+        &self,
+        Parameters(request): Parameters<DiffMessagesRequest>,
+    ) -> Result<Json<DiffMessagesResponse>, McpError> {
+        let left = decode_hex("left_hex", &request.left_hex)?;
+        let right = decode_hex("right_hex", &request.right_hex)?;
+        Ok(Json(self.diff_messages_blocking(left, right, request.message_type)))
+    }
+
+    #[tool(
+        name = "encode_frame",
+        description = "Wrap an encoded message payload in a 6-byte SV2 frame header"
+    )]
+    async fn encode_frame_tool(
+        &self,
+        Parameters(request): Parameters<EncodeFrameRequest>,
+    ) -> Result<Json<EncodeFrameResponse>, McpError> {
+        let payload = decode_hex("payload", &request.payload)?;
+        Ok(Json(self.encode_frame_blocking(
+            request.message_type,
+            payload,
+            request.extension_type.unwrap_or(0),
+            request.channel_msg,
+        )))
+    }
+
+    #[tool(
+        name = "decode_frame",
+        description = "Split an SV2 frame into its header fields and payload"
+    )]
+    async fn decode_frame_tool(
+        &self,
+        Parameters(request): Parameters<DecodeFrameRequest>,
+    ) -> Result<Json<DecodeFrameResponse>, McpError> {
+        let bytes = decode_hex("hex_bytes", &request.hex_bytes)?;
+        Ok(Json(self.decode_frame_blocking(bytes)))
+    }
+
+    #[tool(
+        name = "decode_stream",
+        description = "Split a capture of back-to-back SV2 frames and decode each message"
+    )]
+    async fn decode_stream_tool(
+        &self,
+        Parameters(request): Parameters<DecodeStreamRequest>,
+    ) -> Result<Json<DecodeStreamResponse>, McpError> {
+        let bytes = crate::capture::parse_hex(&request.hex_bytes).map_err(|e| {
+            McpError::invalid_params(format!("Argument hex_bytes is not valid hex: {}", e), None)
+        })?;
+        Ok(Json(self.decode_stream_blocking(bytes)))
+    }
+
+    #[tool(
+        name = "demonstrate_advanced_features",
+        description = "Demonstrate advanced Stratum V2 features using official crates"
+    )]
+    async fn demonstrate_advanced_features_tool(&self) -> String {
+        self.demonstrate_advanced_features().await
+    }
+
+    #[tool(
+        name = "demonstrate_roles_logic",
+        description = "Demonstrate Stratum V2 roles architecture"
+    )]
+    async fn demonstrate_roles_logic_tool(&self) -> String {
+        self.demonstrate_roles_logic().await
+    }
+
+    #[tool(
+        name = "demonstrate_noise_protocol",
+        description = "Demonstrate Noise protocol security features"
+    )]
+    async fn demonstrate_noise_protocol_tool(&self) -> String {
+        self.demonstrate_noise_protocol().await
+    }
+
+    #[tool(
+        name = "demonstrate_buffer_management",
+        description = "Demonstrate buffer management capabilities"
+    )]
+    async fn demonstrate_buffer_management_tool(&self) -> String {
+        self.demonstrate_buffer_management().await
+    }
+}
+
+#[tool_handler(router = Self::tool_router())]
+impl ServerHandler for StratumV2MCPServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                ..Implementation::from_build_env()
+            },
+            instructions: Some(
+                "Tools for exploring the Stratum V2 mining protocol: protocol analysis, message types, message and frame encoding, extensions and TLV fields.".to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+}
+
+fn decode_hex(name: &str, value: &str) -> Result<Vec<u8>, McpError> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|e| {
+        McpError::invalid_params(format!("Argument {} is not valid hex: {}", name, e), None)
+    })
+}
@@ -0,0 +1,481 @@
+//! Catalog of message types, extensions and extension TLV fields.
+//!
+//! Every tool answers from the same `ProtocolRegistry`, so an extension only
+//! needs to be described once. Private extensions can be added from a JSON or
+//! TOML file with the same shape as `ExtensionInfo`:
+//!
+//! ```toml
+//! [[extensions]]
+//! extension_type = 0x4001
+//! name = "Payout Hints"
+//! negotiation_required = true
+//!
+//! [[extensions.tlv_fields]]
+//! field_type = 0x01
+//! name = "payout_address"
+//! data_type = "UTF-8 string"
+//! max_length = 64
+//! messages = ["SubmitSharesExtended"]
+//! ```
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::codec::{self, FieldType, MessageDescriptor, Subprotocol};
+use crate::types::{ExtensionInfo, MessageField, MessageType, ProtocolSpec, TLVFieldInfo};
+
+#[derive(Debug, Clone)]
+pub struct ProtocolRegistry {
+    spec: ProtocolSpec,
+}
+
+impl Default for ProtocolRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ProtocolRegistry {
+    /// The catalog described by the Stratum V2 specification.
+    pub fn builtin() -> Self {
+        ProtocolRegistry {
+            spec: ProtocolSpec {
+                version: "2.0.0".to_string(),
+                description: "Stratum V2 is a comprehensive mining protocol suite with multiple subprotocols, binary message format, noise encryption, and role-based architecture.".to_string(),
+                message_types: builtin_message_types(),
+                extensions: builtin_extensions(),
+                security_features: vec![
+                    "Noise protocol encryption for secure communication".to_string(),
+                    "Binary protocol with efficient message encoding".to_string(),
+                    "TLV (Type-Length-Value) extension support".to_string(),
+                    "Role-based architecture (Pool, Proxy, Miner, Job Declarator)".to_string(),
+                    "Multiple subprotocols (Mining, Job Declaration, Template Distribution)".to_string(),
+                    "Channel-based communication with sequence numbers".to_string(),
+                    "Backward compatibility with Stratum V1 via translator".to_string(),
+                ],
+            },
+        }
+    }
+
+    /// Merge the extensions defined in a `.json` or `.toml` file into the registry.
+    pub fn merge_file(&mut self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read registry file {}", path.display()))?;
+        let file: RegistryFile = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(&contents)
+                .with_context(|| format!("Invalid TOML in registry file {}", path.display()))?,
+            _ => serde_json::from_str(&contents)
+                .with_context(|| format!("Invalid JSON in registry file {}", path.display()))?,
+        };
+        for extension in file.extensions {
+            self.merge_extension(extension);
+        }
+        Ok(())
+    }
+
+    /// Add an extension, or update a known one: its name and description are
+    /// replaced, and its messages and TLV fields are merged, with entries in
+    /// `extension` replacing known TLV fields of the same field_type.
+    pub fn merge_extension(&mut self, extension: ExtensionInfo) {
+        let Some(known) = self
+            .spec
+            .extensions
+            .iter_mut()
+            .find(|known| known.extension_type == extension.extension_type)
+        else {
+            self.spec.extensions.push(extension);
+            return;
+        };
+
+        known.name = extension.name;
+        if !extension.description.is_empty() {
+            known.description = extension.description;
+        }
+        known.negotiation_required = extension.negotiation_required;
+        for message in extension.messages {
+            if !known.messages.contains(&message) {
+                known.messages.push(message);
+            }
+        }
+        for field in extension.tlv_fields {
+            match known.tlv_fields.iter_mut().find(|known| known.field_type == field.field_type) {
+                Some(known) => *known = field,
+                None => known.tlv_fields.push(field),
+            }
+        }
+    }
+
+    pub fn spec(&self) -> &ProtocolSpec {
+        &self.spec
+    }
+
+    pub fn message_types(&self) -> &[MessageType] {
+        &self.spec.message_types
+    }
+
+    /// Catalog entry for a message known to the codec.
+    pub fn message_type(&self, descriptor: &MessageDescriptor) -> Option<&MessageType> {
+        let name = descriptor.lookup_name();
+        self.spec.message_types.iter().find(|message| message.name == name)
+    }
+
+    pub fn extensions(&self) -> &[ExtensionInfo] {
+        &self.spec.extensions
+    }
+
+    pub fn extension(&self, extension_type: u16) -> Option<&ExtensionInfo> {
+        self.spec
+            .extensions
+            .iter()
+            .find(|extension| extension.extension_type == extension_type)
+    }
+
+    pub fn tlv_field(&self, extension_type: u16, field_type: u8) -> Option<&TLVFieldInfo> {
+        self.extension(extension_type)?
+            .tlv_fields
+            .iter()
+            .find(|field| field.field_type == field_type)
+    }
+}
+
+/// Contents of a registry file given with `--registry`.
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    extensions: Vec<ExtensionInfo>,
+}
+
+/// One-line summaries keyed by qualified message name.
+const DESCRIPTIONS: &[(&str, &str)] = &[
+    ("Common::SetupConnection", "Opens a connection, selecting the subprotocol, version range and optional features"),
+    ("Common::SetupConnectionSuccess", "Accepts a connection with the selected version and the flags the server supports"),
+    ("Common::SetupConnectionError", "Rejects a connection, listing the unsupported flags"),
+    ("Common::ChannelEndpointChanged", "Tells downstream that a channel's upstream endpoint changed and its jobs are no longer valid"),
+    ("Common::Reconnect", "Asks the client to reconnect to a new host and port, or the current one when empty"),
+    ("Mining::OpenStandardMiningChannel", "Requests a standard channel, which only rolls the block header"),
+    ("Mining::OpenStandardMiningChannelSuccess", "Opens a standard channel with its target, extranonce prefix and group channel"),
+    ("Mining::OpenMiningChannelError", "Rejects a request to open a standard or extended channel"),
+    ("Mining::OpenExtendedMiningChannel", "Requests an extended channel, which can roll part of the extranonce"),
+    ("Mining::OpenExtendedMiningChannelSuccess", "Opens an extended channel with its target, extranonce size and prefix"),
+    ("Mining::NewMiningJob", "Sends a job for a standard channel with a precomputed merkle root"),
+    ("Mining::UpdateChannel", "Reports a channel's nominal hashrate and the maximum target it accepts"),
+    ("Mining::UpdateChannelError", "Rejects an UpdateChannel request"),
+    ("Mining::CloseChannel", "Closes a channel"),
+    ("Mining::SetExtranoncePrefix", "Changes the extranonce prefix a channel uses for subsequent jobs"),
+    ("Mining::SubmitSharesStandard", "Submits a share found on a standard channel"),
+    ("Mining::SubmitSharesExtended", "Submits a share found on an extended channel, including its extranonce"),
+    ("Mining::SubmitSharesSuccess", "Acknowledges accepted shares up to a sequence number"),
+    ("Mining::SubmitSharesError", "Rejects a submitted share"),
+    ("Mining::NewExtendedMiningJob", "Sends a job for extended and group channels with coinbase parts and merkle path"),
+    ("Mining::SetNewPrevHash", "Activates a future job on a new previous block hash"),
+    ("Mining::SetTarget", "Changes the maximum target accepted for shares on a channel"),
+    ("Mining::SetCustomMiningJob", "Asks the pool to accept a job the client declared"),
+    ("Mining::SetCustomMiningJobSuccess", "Accepts a custom job and assigns its job id"),
+    ("Mining::SetCustomMiningJobError", "Rejects a custom job"),
+    ("Mining::SetGroupChannel", "Assigns standard channels to a group channel"),
+    ("JobDeclaration::AllocateMiningJobToken", "Requests a token that allows declaring a mining job"),
+    ("JobDeclaration::AllocateMiningJobTokenSuccess", "Grants a mining job token together with the pool's coinbase outputs"),
+    ("JobDeclaration::ProvideMissingTransactions", "Asks for transactions of a declared job the Job Declarator does not know"),
+    ("JobDeclaration::ProvideMissingTransactionsSuccess", "Supplies the requested transactions"),
+    ("JobDeclaration::DeclareMiningJob", "Declares a custom job's coinbase and transaction list"),
+    ("JobDeclaration::DeclareMiningJobSuccess", "Accepts a declared job and returns the token to use with SetCustomMiningJob"),
+    ("JobDeclaration::DeclareMiningJobError", "Rejects a declared job"),
+    ("JobDeclaration::PushSolution", "Pushes a block solution to the Job Declarator for propagation"),
+    ("TemplateDistribution::CoinbaseOutputConstraints", "Tells the Template Provider how much space and sigops to reserve for extra coinbase outputs"),
+    ("TemplateDistribution::NewTemplate", "Sends a block template, possibly for a future previous block hash"),
+    ("TemplateDistribution::SetNewPrevHash", "Activates a future template on a new previous block hash"),
+    ("TemplateDistribution::RequestTransactionData", "Requests the transactions of a template"),
+    ("TemplateDistribution::RequestTransactionDataSuccess", "Supplies a template's transactions and excess data"),
+    ("TemplateDistribution::RequestTransactionDataError", "Rejects a transaction data request"),
+    ("TemplateDistribution::SubmitSolution", "Submits a block solution for a template"),
+];
+
+/// One-line field summaries keyed by field name, or by `Message.field` where
+/// a field means something specific to its message.
+const FIELD_DESCRIPTIONS: &[(&str, &str)] = &[
+    ("SetupConnectionSuccess.flags", "Optional features the server supports"),
+    ("SetupConnectionError.flags", "Requested features the server does not support"),
+    ("channel_id", "Mining channel identifier"),
+    ("channel_ids", "Channels that belong to the group channel"),
+    ("coinbase_output_max_additional_sigops", "Sigops the pool's coinbase outputs may add"),
+    ("coinbase_output_max_additional_size", "Bytes the pool's coinbase outputs may add"),
+    ("coinbase_outputs", "Serialized coinbase outputs the pool requires"),
+    ("coinbase_prefix", "Coinbase prefix"),
+    ("coinbase_suffix", "Coinbase suffix"),
+    ("coinbase_tx", "Serialized coinbase transaction"),
+    ("coinbase_tx_input_n_sequence", "nSequence of the coinbase input"),
+    ("coinbase_tx_input_sequence", "nSequence of the coinbase input"),
+    ("coinbase_tx_locktime", "Coinbase transaction locktime"),
+    ("coinbase_tx_outputs", "Serialized coinbase transaction outputs"),
+    ("coinbase_tx_outputs_count", "Number of coinbase transaction outputs"),
+    ("coinbase_tx_prefix", "Coinbase transaction up to the extranonce"),
+    ("coinbase_tx_suffix", "Coinbase transaction after the extranonce"),
+    ("coinbase_tx_value_remaining", "Satoshis left for the coinbase outputs"),
+    ("coinbase_tx_version", "Coinbase transaction version"),
+    ("device_id", "Device identifier"),
+    ("endpoint_host", "Host endpoint"),
+    ("endpoint_port", "Port endpoint"),
+    ("error_code", "Reason for the error"),
+    ("error_details", "Additional error details"),
+    ("excess_data", "Data beyond the transactions, such as the witness reserved value"),
+    ("extranonce", "Extranonce bytes rolled by the miner"),
+    ("extranonce_prefix", "Extranonce prefix assigned to the channel"),
+    ("extranonce_size", "Extranonce bytes the miner may roll"),
+    ("firmware", "Firmware version"),
+    ("flags", "Connection flags"),
+    ("future_template", "Whether this is a future template"),
+    ("group_channel_id", "Group channel identifier"),
+    ("hardware_version", "Hardware version"),
+    ("header_nonce", "Nonce of the block header"),
+    ("header_timestamp", "Timestamp of the block header"),
+    ("job_id", "Job identifier"),
+    ("last_sequence_number", "Sequence number of the last accepted share"),
+    ("max_target", "Largest target the miner accepts"),
+    ("max_version", "Maximum supported protocol version"),
+    ("maximum_target", "Largest target the channel may use"),
+    ("merkle_path", "Merkle path hashes from the coinbase to the merkle root"),
+    ("merkle_root", "Merkle root of the block header"),
+    ("min_extranonce_size", "Smallest extranonce size the miner accepts"),
+    ("min_ntime", "Earliest ntime the job may use"),
+    ("min_version", "Minimum supported protocol version"),
+    ("mining_job_token", "Token identifying an allocated or declared job"),
+    ("n_bits", "Block difficulty in compact form"),
+    ("nbits", "Block difficulty in compact form"),
+    ("new_host", "Host to reconnect to"),
+    ("new_mining_job_token", "Token to use for the declared job"),
+    ("new_port", "Port to reconnect to"),
+    ("new_shares_sum", "Sum of the difficulty of the accepted shares"),
+    ("new_submits_accepted_count", "Number of shares accepted"),
+    ("nominal_hash_rate", "Expected hash rate in h/s"),
+    ("nonce", "Nonce value"),
+    ("ntime", "Block time"),
+    ("prev_hash", "Hash of the previous block"),
+    ("protocol", "Protocol identifier"),
+    ("reason_code", "Reason for closing the channel"),
+    ("request_id", "Identifier matching a response to its request"),
+    ("sequence_number", "Sequence number for ordering"),
+    ("target", "Target a share hash must not exceed"),
+    ("template_id", "Template identifier"),
+    ("token", "Mining job token from job declaration"),
+    ("transaction_list", "Serialized transactions"),
+    ("tx_ids_list", "Transaction ids of the job, excluding the coinbase"),
+    ("unknown_tx_position_list", "Positions of transactions the pool does not have"),
+    ("used_version", "Selected protocol version"),
+    ("user_identifier", "Identity of the user requesting the token"),
+    ("user_identity", "Identity of the miner or account"),
+    ("vendor", "Vendor identifier"),
+    ("version", "Block version"),
+    ("version_rolling_allowed", "Whether the miner may roll the version bits"),
+];
+
+/// Description of `field` in the message `message_name`, empty when none is known.
+fn field_description(message_name: &str, field: &str) -> String {
+    let qualified = format!("{}.{}", message_name, field);
+    FIELD_DESCRIPTIONS
+        .iter()
+        .find(|(name, _)| *name == qualified)
+        .or_else(|| FIELD_DESCRIPTIONS.iter().find(|(name, _)| *name == field))
+        .map(|(_, description)| description.to_string())
+        .unwrap_or_default()
+}
+
+/// Every message the codec supports, described from its wire layout.
+fn builtin_message_types() -> Vec<MessageType> {
+    codec::messages()
+        .map(|descriptor| {
+            let qualified_name = descriptor.qualified_name();
+            let description = DESCRIPTIONS
+                .iter()
+                .find(|(name, _)| *name == qualified_name)
+                .map(|(_, description)| description.to_string())
+                .unwrap_or_default();
+            MessageType {
+                name: descriptor.lookup_name(),
+                subprotocol: subprotocol_name(descriptor.subprotocol).to_string(),
+                msg_type: descriptor.msg_type,
+                channel_msg: descriptor.channel_msg,
+                direction: descriptor.direction.label().to_string(),
+                fields: descriptor
+                    .fields
+                    .iter()
+                    .map(|(name, field_type)| {
+                        let (min_size, max_size) = field_type.size_range();
+                        MessageField {
+                            name: name.to_string(),
+                            field_type: field_type.spec_name(),
+                            description: field_description(descriptor.name, name),
+                            // The encoder treats a missing OPTION field as absent
+                            required: !matches!(field_type, FieldType::Sv2Option(_)),
+                            min_size,
+                            max_size,
+                        }
+                    })
+                    .collect(),
+                description,
+            }
+        })
+        .collect()
+}
+
+fn subprotocol_name(subprotocol: Subprotocol) -> &'static str {
+    match subprotocol {
+        Subprotocol::Common => "common",
+        Subprotocol::Mining => "mining",
+        Subprotocol::JobDeclaration => "job_declaration",
+        Subprotocol::TemplateDistribution => "template_distribution",
+    }
+}
+
+fn builtin_extensions() -> Vec<ExtensionInfo> {
+    vec![
+        ExtensionInfo {
+            extension_type: 0x0001,
+            name: "Extensions Negotiation".to_string(),
+            description: "This extension defines the basic protocol for requesting and negotiating support for other protocol extensions between clients and servers.".to_string(),
+            negotiation_required: true,
+            messages: vec![
+                "RequestExtensions".to_string(),
+                "RequestExtensions.Success".to_string(),
+                "RequestExtensions.Error".to_string(),
+            ],
+            tlv_fields: vec![],
+        },
+        ExtensionInfo {
+            extension_type: 0x0002,
+            name: "Worker-Specific Hashrate Tracking".to_string(),
+            description: "This extension modifies the existing SubmitSharesExtended message by introducing a new TLV field that contains the user_identity (worker name).".to_string(),
+            negotiation_required: true,
+            messages: vec![],
+            tlv_fields: vec![TLVFieldInfo {
+                field_type: 0x01,
+                name: "user_identity".to_string(),
+                data_type: "UTF-8 string".to_string(),
+                max_length: Some(32),
+                description: "Worker name/identifier for hashrate tracking".to_string(),
+                messages: vec!["SubmitSharesExtended".to_string()],
+            }],
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Write `contents` to a file named `name` in a per-process temp location.
+    fn fixture(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("sv2-registry-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn merges_json_extensions() {
+        let path = fixture(
+            "payout.json",
+            r#"{"extensions": [{
+                "extension_type": 16385,
+                "name": "Payout Hints",
+                "negotiation_required": false,
+                "tlv_fields": [{"field_type": 1, "name": "payout_address", "data_type": "UTF-8 string"}]
+            }]}"#,
+        );
+        let mut registry = ProtocolRegistry::builtin();
+        registry.merge_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(registry.extensions().len(), builtin_extensions().len() + 1);
+        let field = registry.tlv_field(0x4001, 0x01).unwrap();
+        assert_eq!(field.name, "payout_address");
+        assert!(field.messages.is_empty());
+        assert!(!registry.extension(0x4001).unwrap().negotiation_required);
+    }
+
+    #[test]
+    fn merges_toml_extensions() {
+        let path = fixture(
+            "payout.toml",
+            r#"
+            [[extensions]]
+            extension_type = 0x4001
+            name = "Payout Hints"
+            negotiation_required = true
+
+            [[extensions.tlv_fields]]
+            field_type = 0x01
+            name = "payout_address"
+            data_type = "UTF-8 string"
+            max_length = 64
+            messages = ["SubmitSharesExtended"]
+            "#,
+        );
+        let mut registry = ProtocolRegistry::builtin();
+        registry.merge_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let field = registry.tlv_field(0x4001, 0x01).unwrap();
+        assert_eq!(field.max_length, Some(64));
+        assert_eq!(field.messages, ["SubmitSharesExtended"]);
+    }
+
+    #[test]
+    fn overrides_builtin_extensions() {
+        let path = fixture(
+            "override.toml",
+            r#"
+            [[extensions]]
+            extension_type = 0x0002
+            name = "Worker Tracking"
+            negotiation_required = true
+            messages = ["SubmitSharesExtended"]
+
+            [[extensions.tlv_fields]]
+            field_type = 0x01
+            name = "user_identity"
+            data_type = "UTF-8 string"
+            max_length = 64
+
+            [[extensions.tlv_fields]]
+            field_type = 0x02
+            name = "device_model"
+            data_type = "UTF-8 string"
+            "#,
+        );
+        let mut registry = ProtocolRegistry::builtin();
+        registry.merge_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(registry.extensions().len(), builtin_extensions().len());
+        let extension = registry.extension(0x0002).unwrap();
+        assert_eq!(extension.name, "Worker Tracking");
+        // An empty description keeps the builtin one
+        assert!(extension.description.starts_with("This extension modifies"));
+        assert_eq!(extension.messages, ["SubmitSharesExtended"]);
+        assert_eq!(extension.tlv_fields.len(), 2);
+        let user_identity = registry.tlv_field(0x0002, 0x01).unwrap();
+        assert_eq!(user_identity.max_length, Some(64));
+        assert!(user_identity.messages.is_empty());
+        assert_eq!(registry.tlv_field(0x0002, 0x02).unwrap().name, "device_model");
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let mut registry = ProtocolRegistry::builtin();
+        for (name, contents, expected) in [
+            ("broken.toml", "[[extensions]]\nextension_type = ", "Invalid TOML"),
+            ("broken.json", r#"{"extensions": [{"name": "No type"}]}"#, "Invalid JSON"),
+        ] {
+            let path = fixture(name, contents);
+            let error = registry.merge_file(&path).unwrap_err();
+            std::fs::remove_file(&path).unwrap();
+            assert!(error.to_string().starts_with(expected), "{}", error);
+        }
+        assert!(registry.merge_file(std::env::temp_dir().join("sv2-registry-missing.json")).is_err());
+        assert_eq!(registry.extensions().len(), builtin_extensions().len());
+    }
+}
@@ -0,0 +1,330 @@
+//! Interpretation of TLV values according to the `data_type` an extension
+//! registers for the field.
+
+use base64::Engine;
+use serde_json::{json, Value};
+
+use crate::codec::{self, MessageDescriptor};
+use crate::types::{ExtensionInfo, TLVFieldInfo};
+
+/// How a TLV value is encoded, derived from `TLVFieldInfo::data_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlvDataType {
+    Utf8,
+    U8,
+    U16,
+    U32,
+    U64,
+    /// Little-endian unsigned integer of 1 to 8 bytes
+    Integer,
+    Bytes,
+}
+
+impl TlvDataType {
+    /// Interpret a registry `data_type` such as "UTF-8 string" or "U32".
+    /// Anything unrecognized is treated as opaque bytes.
+    pub fn from_data_type(data_type: &str) -> Self {
+        let normalized: String = data_type
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        match normalized.as_str() {
+            "utf8" | "utf8string" | "string" | "str" | "str0255" | "text" => TlvDataType::Utf8,
+            "u8" => TlvDataType::U8,
+            "u16" => TlvDataType::U16,
+            "u32" => TlvDataType::U32,
+            "u64" => TlvDataType::U64,
+            "integer" | "int" | "uint" | "unsignedinteger" => TlvDataType::Integer,
+            _ => TlvDataType::Bytes,
+        }
+    }
+
+    /// Decode a value: strings as text, integers as numbers, bytes as hex.
+    pub fn decode(&self, value: &[u8]) -> Result<Value, String> {
+        let width = match self {
+            TlvDataType::Utf8 => {
+                return std::str::from_utf8(value)
+                    .map(|text| json!(text))
+                    .map_err(|e| format!("value is not valid UTF-8: {}", e));
+            }
+            TlvDataType::Bytes => return Ok(json!(hex::encode(value))),
+            TlvDataType::U8 => Some(1),
+            TlvDataType::U16 => Some(2),
+            TlvDataType::U32 => Some(4),
+            TlvDataType::U64 => Some(8),
+            TlvDataType::Integer => None,
+        };
+        match width {
+            Some(width) if value.len() != width => {
+                Err(format!("value must be {} bytes, found {}", width, value.len()))
+            }
+            None if value.is_empty() || value.len() > 8 => {
+                Err(format!("integer value must be 1 to 8 bytes, found {}", value.len()))
+            }
+            _ => {
+                let mut bytes = [0u8; 8];
+                bytes[..value.len()].copy_from_slice(value);
+                Ok(json!(u64::from_le_bytes(bytes)))
+            }
+        }
+    }
+}
+
+/// Text form of a value given to `create_tlv_field`, and how it becomes bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlvValueFormat {
+    Utf8,
+    Hex,
+    Base64,
+    U8,
+    U16,
+    U32,
+    U64,
+    /// Integer in the smallest of 1, 2, 4 or 8 bytes that holds it
+    Integer,
+}
+
+impl TlvValueFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().replace(['-', '_'], "").as_str() {
+            "utf8" | "string" => Ok(TlvValueFormat::Utf8),
+            "hex" => Ok(TlvValueFormat::Hex),
+            "base64" => Ok(TlvValueFormat::Base64),
+            "u8" => Ok(TlvValueFormat::U8),
+            "u16" => Ok(TlvValueFormat::U16),
+            "u32" => Ok(TlvValueFormat::U32),
+            "u64" => Ok(TlvValueFormat::U64),
+            "integer" | "int" => Ok(TlvValueFormat::Integer),
+            _ => Err(format!(
+                "Unknown value type {}: use utf8, hex, base64, u8, u16, u32, u64 or integer",
+                name
+            )),
+        }
+    }
+
+    /// Format matching a registered data type; binary values are given as hex.
+    pub fn for_data_type(data_type: TlvDataType) -> Self {
+        match data_type {
+            TlvDataType::Utf8 => TlvValueFormat::Utf8,
+            TlvDataType::U8 => TlvValueFormat::U8,
+            TlvDataType::U16 => TlvValueFormat::U16,
+            TlvDataType::U32 => TlvValueFormat::U32,
+            TlvDataType::U64 => TlvValueFormat::U64,
+            TlvDataType::Integer => TlvValueFormat::Integer,
+            TlvDataType::Bytes => TlvValueFormat::Hex,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TlvValueFormat::Utf8 => "utf8",
+            TlvValueFormat::Hex => "hex",
+            TlvValueFormat::Base64 => "base64",
+            TlvValueFormat::U8 => "u8",
+            TlvValueFormat::U16 => "u16",
+            TlvValueFormat::U32 => "u32",
+            TlvValueFormat::U64 => "u64",
+            TlvValueFormat::Integer => "integer",
+        }
+    }
+
+    /// Value bytes for `value`; integers are little-endian and accept decimal
+    /// or `0x` hex.
+    pub fn encode(&self, value: &str) -> Result<Vec<u8>, String> {
+        let width = match self {
+            TlvValueFormat::Utf8 => return Ok(value.as_bytes().to_vec()),
+            TlvValueFormat::Hex => {
+                return hex::decode(value.trim_start_matches("0x")).map_err(|e| e.to_string());
+            }
+            TlvValueFormat::Base64 => {
+                return base64::engine::general_purpose::STANDARD
+                    .decode(value)
+                    .map_err(|e| e.to_string());
+            }
+            TlvValueFormat::U8 => 1,
+            TlvValueFormat::U16 => 2,
+            TlvValueFormat::U32 => 4,
+            TlvValueFormat::U64 | TlvValueFormat::Integer => 8,
+        };
+        let parsed = match value.strip_prefix("0x") {
+            Some(digits) => u64::from_str_radix(digits, 16),
+            None => value.parse::<u64>(),
+        }
+        .map_err(|_| format!("{} is not an unsigned integer", value))?;
+        let width = match self {
+            TlvValueFormat::Integer => [1, 2, 4, 8]
+                .into_iter()
+                .find(|width| *width == 8 || parsed >> (8 * width) == 0)
+                .unwrap_or(8),
+            _ => width,
+        };
+        if width < 8 && parsed >> (8 * width) != 0 {
+            return Err(format!("{} does not fit in {}", value, self.name()));
+        }
+        Ok(parsed.to_le_bytes()[..width].to_vec())
+    }
+}
+
+/// Check that a field may be sent in a session that negotiated
+/// `negotiated_extensions`, attached to `message`. Either check is skipped
+/// when its context is not given.
+pub fn check_session(
+    extension: &ExtensionInfo,
+    field: &TLVFieldInfo,
+    negotiated_extensions: Option<&[u16]>,
+    message: Option<&MessageDescriptor>,
+) -> Result<(), String> {
+    if extension.negotiation_required
+        && negotiated_extensions.is_some_and(|negotiated| !negotiated.contains(&extension.extension_type))
+    {
+        return Err(format!(
+            "{} extension (0x{:04x}) was not negotiated",
+            extension.name, extension.extension_type
+        ));
+    }
+    if let Some(message) = message {
+        let allowed = field.messages.is_empty()
+            || field.messages.iter().any(|name| {
+                codec::lookup(name).is_ok_and(|allowed| allowed.qualified_name() == message.qualified_name())
+            });
+        if !allowed {
+            return Err(format!(
+                "{} is only valid on {}, not {}",
+                field.name,
+                field.messages.join(", "),
+                message.lookup_name()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Complete TLV encoding: extension_type (U16), field_type (U8), length (U16)
+/// and the value.
+pub fn encode_tlv(extension_type: u16, field_type: u8, value: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(5 + value.len());
+    bytes.extend_from_slice(&extension_type.to_le_bytes());
+    bytes.push(field_type);
+    bytes.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(value);
+    bytes
+}
+
+/// A complete TLV field found in a TLV region.
+pub struct RawTlv<'a> {
+    pub extension_type: u16,
+    pub field_type: u8,
+    pub value: &'a [u8],
+}
+
+/// Split a TLV region into its complete fields. Splitting stops at bytes
+/// that do not form a complete field; their offset within `bytes` is returned
+/// with why, the offset in the message counted from `base_offset`.
+pub fn split_fields(bytes: &[u8], base_offset: usize) -> (Vec<RawTlv<'_>>, Option<(usize, String)>) {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+
+    while offset < bytes.len() {
+        let remaining = bytes.len() - offset;
+        if remaining < 5 {
+            let error = format!(
+                "{} trailing bytes at offset {} are shorter than a TLV header: {}",
+                remaining,
+                base_offset + offset,
+                hex::encode(&bytes[offset..])
+            );
+            return (fields, Some((offset, error)));
+        }
+
+        let length = u16::from_le_bytes([bytes[offset + 3], bytes[offset + 4]]) as usize;
+        if offset + 5 + length > bytes.len() {
+            let error = format!(
+                "Insufficient bytes for TLV value at offset {} (need {}, have {}): {}",
+                base_offset + offset,
+                length,
+                remaining - 5,
+                hex::encode(&bytes[offset..])
+            );
+            return (fields, Some((offset, error)));
+        }
+
+        fields.push(RawTlv {
+            extension_type: u16::from_le_bytes([bytes[offset], bytes[offset + 1]]),
+            field_type: bytes[offset + 2],
+            value: &bytes[offset + 5..offset + 5 + length],
+        });
+        offset += 5 + length;
+    }
+
+    (fields, None)
+}
+
+/// Decode a value of a registered field and check it against the field's
+/// limits, returning the decoded value or why it is invalid.
+pub fn decode_field_value(field: &TLVFieldInfo, value: &[u8]) -> Result<Value, String> {
+    if let Some(max_length) = field.max_length {
+        if value.len() > max_length {
+            return Err(format!("{} must be {} bytes or less", field.name, max_length));
+        }
+    }
+    TlvDataType::from_data_type(&field.data_type)
+        .decode(value)
+        .map_err(|e| format!("{}: {}", field.name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_identity() -> (ExtensionInfo, TLVFieldInfo) {
+        let field = TLVFieldInfo {
+            field_type: 0x01,
+            name: "user_identity".to_string(),
+            data_type: "UTF-8 string".to_string(),
+            max_length: Some(32),
+            description: String::new(),
+            messages: vec!["SubmitSharesExtended".to_string()],
+        };
+        let extension = ExtensionInfo {
+            extension_type: 0x0002,
+            name: "Worker-Specific Hashrate Tracking".to_string(),
+            description: String::new(),
+            negotiation_required: true,
+            messages: Vec::new(),
+            tlv_fields: vec![field.clone()],
+        };
+        (extension, field)
+    }
+
+    #[test]
+    fn check_session_requires_negotiation() {
+        let (extension, field) = user_identity();
+        assert!(check_session(&extension, &field, Some(&[0x0002]), None).is_ok());
+        assert!(check_session(&extension, &field, None, None).is_ok());
+        let error = check_session(&extension, &field, Some(&[0x0003]), None).unwrap_err();
+        assert_eq!(error, "Worker-Specific Hashrate Tracking extension (0x0002) was not negotiated");
+
+        let optional = ExtensionInfo {
+            negotiation_required: false,
+            ..extension
+        };
+        assert!(check_session(&optional, &field, Some(&[]), None).is_ok());
+    }
+
+    #[test]
+    fn check_session_limits_fields_to_their_messages() {
+        let (extension, field) = user_identity();
+        let extended = codec::lookup("SubmitSharesExtended").unwrap();
+        let standard = codec::lookup("SubmitSharesStandard").unwrap();
+        assert!(check_session(&extension, &field, None, Some(extended)).is_ok());
+        let error = check_session(&extension, &field, None, Some(standard)).unwrap_err();
+        assert_eq!(error, "user_identity is only valid on SubmitSharesExtended, not SubmitSharesStandard");
+
+        let anywhere = TLVFieldInfo {
+            messages: Vec::new(),
+            ..field
+        };
+        assert!(check_session(&extension, &anywhere, None, Some(standard)).is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -5,7 +6,7 @@ use std::collections::HashMap;
 // Type aliases for clarity in specifications
 pub type MessageTypeEnum = String;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ProtocolSpec {
     pub version: String,
     pub description: String,
@@ -14,101 +15,532 @@ pub struct ProtocolSpec {
     pub security_features: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MessageType {
+    /// Name accepted by encode_message, qualified when several subprotocols use it
     pub name: String,
+    /// "common", "mining", "job_declaration" or "template_distribution"
+    pub subprotocol: String,
+    pub msg_type: u8,
+    /// Whether the channel_msg bit is set in the frame header
+    pub channel_msg: bool,
     pub direction: String,
+    /// Fields in wire order
     pub fields: Vec<MessageField>,
     pub description: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MessageField {
     pub name: String,
+    /// SV2 data type, e.g. "U32" or "SEQ0_255[U256]"
     pub field_type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub description: String,
     pub required: bool,
+    /// Smallest encoded size in bytes, including any length prefix
+    pub min_size: usize,
+    /// Largest encoded size in bytes, including any length prefix
+    pub max_size: usize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExtensionInfo {
     pub extension_type: u16,
     pub name: String,
+    #[serde(default)]
     pub description: String,
     pub negotiation_required: bool,
+    #[serde(default)]
     pub messages: Vec<String>,
+    #[serde(default)]
     pub tlv_fields: Vec<TLVFieldInfo>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TLVFieldInfo {
     pub field_type: u8,
     pub name: String,
     pub data_type: String,
+    #[serde(default)]
     pub max_length: Option<usize>,
+    #[serde(default)]
     pub description: String,
+    /// Messages the field may be attached to; any message when empty
+    #[serde(default)]
+    pub messages: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TLVField {
     pub extension_type: u16,
     pub field_type: u8,
     pub length: u16,
-    pub value: Vec<u8>,
+    /// Value bytes as a hex string
+    pub value: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CreateTLVFieldResponse {
     pub tlv_field: Option<TLVField>,
+    /// How the value was read: utf8, hex, base64, u8, u16, u32, u64 or integer
+    pub value_type: Option<String>,
+    /// Complete TLV encoding (type, length, value) as a hex string
     pub encoded_bytes: Option<String>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ParsedTLVField {
+    pub extension_type: u16,
+    pub field_type: u8,
+    pub length: u16,
+    /// Raw value bytes as a hex string
+    pub value: String,
+    /// Name of the extension, if registered
+    pub extension_name: Option<String>,
+    /// Name of the field, if registered
+    pub field_name: Option<String>,
+    /// Registered data type of the field
+    pub data_type: Option<String>,
+    /// Value decoded according to the registered data type
+    pub decoded_value: Option<Value>,
+    /// Whether the extension and field type are registered
+    pub known: bool,
+    /// Whether the value satisfies the registered field definition; null for
+    /// unknown fields, which are not checked
+    pub valid: Option<bool>,
+    /// Why the value is invalid, or why the field is unknown
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BuildTLVListResponse {
+    /// Fields in the order they are encoded
+    pub tlv_fields: Vec<ParsedTLVField>,
+    /// Concatenated TLV fields as a hex string
+    pub encoded_bytes: Option<String>,
+    /// Length of the encoded TLV block in bytes
+    pub total_length: Option<usize>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TrailingBytes {
+    /// Offset of the first byte that is not part of a complete TLV field
+    pub offset: usize,
+    pub hex: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ParseTLVFieldsResponse {
-    pub tlv_fields: Vec<TLVField>,
+    pub tlv_fields: Vec<ParsedTLVField>,
+    /// Bytes after the last complete TLV field
+    pub trailing_bytes: Option<TrailingBytes>,
     pub errors: Vec<String>,
     pub parsed_successfully: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TLVValidationResult {
     pub valid: bool,
     pub error: Option<String>,
     pub field_info: Option<HashMap<String, Value>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ValidateTLVFieldResponse {
     pub validation: TLVValidationResult,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GetExtensionInfoResponse {
     pub extension_info: Option<ExtensionInfo>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GenerateTestMessageResponse {
     pub message: Option<Value>,
+    /// What a hand-written sample illustrates; absent for generated messages
+    pub description: Option<String>,
+    /// Encoded message payload as a hex string, when requested
+    pub encoded_bytes: Option<String>,
+    /// Seed that reproduces a random message
+    pub seed: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TestVector {
+    /// What the vector exercises, e.g. "merkle_path with 255 items"
+    pub label: String,
+    /// "boundary" for valid encodings at a type's limits, "malformed" for invalid ones
+    pub category: String,
+    /// Whether hex is a complete frame rather than a bare message payload
+    pub framed: bool,
+    pub hex: String,
+    /// Outcome a conforming decoder should produce: "decodes" or "rejected"
+    pub expected: String,
+    /// Outcome this server's decoder actually produced, in the same terms;
+    /// differs from expected only when the decoder disagrees with the spec
+    pub outcome: String,
+    /// Message this server decodes the bytes to; omitted for vectors over
+    /// 1 KiB, where it would only repeat hex
+    pub message: Option<Value>,
+    /// Error this server reports for the bytes
+    pub decoder_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GenerateTestVectorsResponse {
+    pub message_type: Option<String>,
+    pub vectors: Vec<TestVector>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MessageEncodingResult {
     pub success: bool,
+    /// Message type the payload was decoded as
+    pub message_type: Option<String>,
+    /// Encoded message payload, including any TLV fields, as a hex string
     pub encoded_bytes: Option<String>,
+    /// Complete frame around the payload, when framing was requested
+    pub frame_bytes: Option<String>,
     pub decoded_message: Option<Value>,
+    /// TLV fields following the message fields, if any
+    pub tlvs: Option<ParseTLVFieldsResponse>,
+    /// Byte-offset map of the input, when annotation was requested
+    pub annotations: Option<Vec<ByteAnnotation>>,
+    /// Fields that changed across an encode/decode round trip, when verification was requested
+    pub mismatches: Option<Vec<FieldMismatch>>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ByteAnnotation {
+    /// Offset of the first byte within the decoded input
+    pub offset: usize,
+    pub length: usize,
+    /// Field path, e.g. "merkle_path[1]" or "coinbase_prefix.length"
+    pub field: String,
+    /// SV2 data type of the bytes, e.g. "U32" or "B0_64K length"
+    pub data_type: String,
+    /// Raw bytes as a hex string
+    pub hex: String,
+    /// Interpreted value
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FieldMismatch {
+    /// Field path, e.g. "user_identity" or "merkle_path[1]"
+    pub field: String,
+    /// "dropped" (not part of the message), "truncated" or "coerced"
+    pub kind: String,
+    /// Value as given to the encoder
+    pub input: Value,
+    /// Value decoded from the encoded bytes
+    pub decoded: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetMessageInfoResponse {
+    pub message_info: Option<MessageType>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetMessageSchemaResponse {
+    pub message_type: Option<String>,
+    /// JSON Schema (draft 2020-12) of the message's JSON form
+    pub schema: Option<Value>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListExtensionsResponse {
+    pub extensions: Vec<ExtensionInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListMessageTypesResponse {
+    pub message_types: Vec<MessageType>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FieldDiff {
+    /// Field path as used in annotations, e.g. "header.msg_length" or "tlv[0].value"
+    pub field: String,
+    /// Field as found in the left input, if present there
+    pub left: Option<ByteAnnotation>,
+    /// Field as found in the right input, if present there
+    pub right: Option<ByteAnnotation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiffMessagesResponse {
+    pub success: bool,
+    pub left_message_type: Option<String>,
+    pub right_message_type: Option<String>,
+    /// Whether both inputs are byte-for-byte identical
+    pub identical: bool,
+    /// Fields whose raw bytes differ or that are present on one side only
+    pub differences: Vec<FieldDiff>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EncodeMessageResponse {
     pub result: MessageEncodingResult,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DecodeMessageResponse {
     pub result: MessageEncodingResult,
-} 
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FrameHeader {
+    /// Extension type with the channel_msg bit cleared (0 for the core protocol)
+    pub extension_type: u16,
+    /// Whether the channel_msg bit (bit 15 of extension_type) is set
+    pub channel_msg: bool,
+    pub msg_type: u8,
+    /// Payload length declared by the 24-bit msg_length field
+    pub msg_length: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EncodeFrameResponse {
+    pub success: bool,
+    pub header: Option<FrameHeader>,
+    /// Complete frame (header followed by payload) as a hex string
+    pub frame_bytes: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DecodeFrameResponse {
+    pub success: bool,
+    pub header: Option<FrameHeader>,
+    /// Number of payload bytes actually present after the header
+    pub actual_length: Option<usize>,
+    /// Whether msg_length matches the number of payload bytes present
+    pub length_matches: Option<bool>,
+    /// Bytes following the header as a hex string
+    pub payload: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StreamFrame {
+    /// Byte offset of the frame header within the stream
+    pub offset: usize,
+    pub header: FrameHeader,
+    /// Message type detected from the header
+    pub message_type: Option<String>,
+    pub decoded_message: Option<Value>,
+    /// Frame payload as a hex string
+    pub payload: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DecodeStreamResponse {
+    pub frames: Vec<StreamFrame>,
+    /// Byte offset of trailing bytes that do not form a complete frame
+    pub leftover_offset: Option<usize>,
+    /// Trailing partial-frame bytes as a hex string
+    pub leftover_bytes: Option<String>,
+    pub errors: Vec<String>,
+    pub parsed_successfully: bool,
+}
+
+// Tool request parameters
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetExtensionInfoRequest {
+    /// Extension type identifier (e.g. 2 for Worker-Specific Hashrate Tracking)
+    pub extension_type: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateTLVFieldRequest {
+    /// Extension type identifier
+    pub extension_type: u16,
+    /// TLV field type within the extension
+    pub field_type: u8,
+    /// Field value, read according to value_type
+    pub value: String,
+    /// How to read the value: utf8, hex, base64, or a little-endian integer
+    /// (u8, u16, u32, u64, decimal or 0x hex). Defaults to the field's
+    /// registered data type, or utf8 for unregistered fields
+    #[serde(default)]
+    pub value_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BuildTLVListRequest {
+    /// Fields to encode, in any order
+    pub fields: Vec<CreateTLVFieldRequest>,
+    /// Extension types agreed via RequestExtensions.Success; when omitted,
+    /// every registered extension is treated as negotiated
+    #[serde(default)]
+    pub negotiated_extensions: Option<Vec<u16>>,
+    /// Message the block is appended to; fields restricted to other messages
+    /// are rejected
+    #[serde(default)]
+    pub message_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ParseTLVFieldsRequest {
+    /// Concatenated TLV fields as a hex string (e.g. "0200010900776f726b6572313233")
+    pub hex_bytes: String,
+    /// Report bytes that do not form a complete TLV field without failing the parse
+    #[serde(default)]
+    pub lenient: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ValidateTLVFieldRequest {
+    /// Extension type identifier
+    pub extension_type: u16,
+    /// TLV field type within the extension
+    pub field_type: u8,
+    /// Field value, read according to value_type
+    pub value: String,
+    /// How to read the value, as in create_tlv_field. Defaults to the field's
+    /// registered data type
+    #[serde(default)]
+    pub value_type: Option<String>,
+    /// Extension types agreed via RequestExtensions.Success; when omitted,
+    /// negotiation is not checked
+    #[serde(default)]
+    pub negotiated_extensions: Option<Vec<u16>>,
+    /// Message the field is attached to (e.g. SubmitSharesExtended); when
+    /// omitted, the field's message restrictions are not checked
+    #[serde(default)]
+    pub message_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GenerateTestMessageRequest {
+    /// Stratum V2 message name (e.g. SetupConnection) or msg_type
+    pub message_type: String,
+    /// Field values replacing the generated ones, e.g. {"channel_id": 7}
+    #[serde(default)]
+    pub overrides: Option<Value>,
+    /// Fill every field with a random value within its type's bounds
+    #[serde(default)]
+    pub random: bool,
+    /// Seed for random generation, only valid with `random`; a random seed is
+    /// used and returned when omitted
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Also return the encoded message payload
+    #[serde(default)]
+    pub encode: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GenerateTestVectorsRequest {
+    /// Stratum V2 message name (e.g. SetupConnection) or msg_type
+    pub message_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EncodeMessageRequest {
+    /// Stratum V2 message name (e.g. SetupConnection)
+    pub message_type: String,
+    /// Message fields as a JSON object keyed by field name
+    pub message: Value,
+    /// Decode the encoded bytes again and report fields that did not survive the round trip
+    #[serde(default)]
+    pub verify: bool,
+    /// TLV fields to append after the message fields, as accepted by build_tlv_list
+    #[serde(default)]
+    pub tlvs: Vec<CreateTLVFieldRequest>,
+    /// Also return the message as a complete frame, msg_length including the TLVs
+    #[serde(default)]
+    pub frame: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetMessageInfoRequest {
+    /// Message name (e.g. NewTemplate, Mining::SetNewPrevHash) or msg_type (e.g. 0x71)
+    pub message_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetMessageSchemaRequest {
+    /// Message name (e.g. SubmitSharesExtended) or msg_type (e.g. 0x1b)
+    pub message_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ListMessageTypesRequest {
+    /// Only list messages of this subprotocol: common, mining, job_declaration
+    /// or template_distribution
+    #[serde(default)]
+    pub subprotocol: Option<String>,
+    /// Only list messages sent in this direction: client_to_server or
+    /// server_to_client (messages sent both ways match either)
+    #[serde(default)]
+    pub direction: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DiffMessagesRequest {
+    /// First message as a hex string
+    pub left_hex: String,
+    /// Second message as a hex string
+    pub right_hex: String,
+    /// Stratum V2 message name when both inputs are bare payloads; omit to
+    /// detect each message from its frame header
+    #[serde(default)]
+    pub message_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DecodeMessageRequest {
+    /// Stratum V2 message name (e.g. SetupConnection); omit to detect it from
+    /// the frame header, in which case hex_bytes must be a complete frame
+    #[serde(default)]
+    pub message_type: Option<String>,
+    /// Encoded message payload, or a complete frame, as a hex string
+    pub hex_bytes: String,
+    /// Also return the offset, length, raw bytes and value of every field
+    #[serde(default)]
+    pub annotate: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EncodeFrameRequest {
+    /// Stratum V2 message name, or a numeric msg_type such as "0x1a"
+    pub message_type: String,
+    /// Encoded message payload as a hex string
+    pub payload: String,
+    /// Extension type for the header; defaults to 0 (core protocol)
+    #[serde(default)]
+    pub extension_type: Option<u16>,
+    /// Override the channel_msg bit; defaults to the message's own setting
+    #[serde(default)]
+    pub channel_msg: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DecodeFrameRequest {
+    /// Complete frame (6-byte header followed by payload) as a hex string
+    pub hex_bytes: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DecodeStreamRequest {
+    /// Back-to-back frames as a hex string; whitespace is ignored. Capture
+    /// files can only be read through the CLI's --file
+    pub hex_bytes: String,
+}